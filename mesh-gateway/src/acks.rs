@@ -0,0 +1,70 @@
+use crate::types::AckRequest;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Senders waiting for delivery acks, keyed by message_id
+#[derive(Clone, Default)]
+pub struct PendingAcks {
+    waiters: Arc<Mutex<HashMap<String, oneshot::Sender<AckRequest>>>>,
+}
+
+impl PendingAcks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start waiting for the ack of `message_id`
+    /// Register before forwarding the message so a fast ack isn't missed.
+    pub fn register(&self, message_id: &str) -> oneshot::Receiver<AckRequest> {
+        let (sender, receiver) = oneshot::channel();
+        self.waiters.lock().unwrap().insert(message_id.to_string(), sender);
+        receiver
+    }
+
+    /// Hand an arrived ack to its waiter, returning false if nobody was waiting
+    pub fn resolve(&self, ack: AckRequest) -> bool {
+        let waiter = self.waiters.lock().unwrap().remove(&ack.message_id);
+        match waiter {
+            Some(sender) => sender.send(ack).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Stop waiting for `message_id` (e.g. after a timeout)
+    pub fn cancel(&self, message_id: &str) {
+        self.waiters.lock().unwrap().remove(message_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DEFAULT_MESSAGE_TTL;
+
+    fn ack(message_id: &str) -> AckRequest {
+        AckRequest {
+            message_id: message_id.to_string(),
+            origin: "gateway-a".to_string(),
+            from: "gateway-c".to_string(),
+            route: vec!["gateway-a".to_string(), "gateway-b".to_string(), "gateway-c".to_string()],
+            ttl: DEFAULT_MESSAGE_TTL,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ack_reaches_registered_waiter_only() {
+        let pending = PendingAcks::new();
+        let waiter = pending.register("m1");
+
+        assert!(!pending.resolve(ack("m2")));
+        assert!(pending.resolve(ack("m1")));
+        assert_eq!(waiter.await.unwrap().from, "gateway-c");
+
+        // Resolved (or cancelled) waiters are forgotten
+        assert!(!pending.resolve(ack("m1")));
+        pending.register("m3");
+        pending.cancel("m3");
+        assert!(!pending.resolve(ack("m3")));
+    }
+}
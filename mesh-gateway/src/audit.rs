@@ -0,0 +1,170 @@
+use crate::types::AuditRecord;
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+/// Rotated audit files kept besides the current one (`<path>.1` is the newest)
+const ROTATED_FILES: usize = 5;
+
+/// Append-only JSONL log of the messages this node handled
+///
+/// Records are handed to a writer thread, so request handlers never wait on
+/// the disk. The thread flushes whenever it runs out of queued records, and
+/// rotates the file once it grows past `max_bytes`.
+#[derive(Clone)]
+pub struct AuditLog {
+    records: mpsc::Sender<AuditRecord>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log at `path` and start its writer thread
+    pub fn open(path: impl AsRef<Path>, max_bytes: u64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let writer = AuditWriter::open(path.clone(), max_bytes)?;
+
+        let (records, queue) = mpsc::channel();
+        thread::Builder::new()
+            .name("audit-log".to_string())
+            .spawn(move || writer.run(queue))
+            .with_context(|| format!("Failed to start audit log writer for {:?}", path))?;
+
+        Ok(Self { records })
+    }
+
+    /// Queue a record for writing
+    pub fn record(&self, record: AuditRecord) {
+        if self.records.send(record).is_err() {
+            tracing::warn!("Audit log writer has stopped; record dropped");
+        }
+    }
+}
+
+struct AuditWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    file: BufWriter<File>,
+    written: u64,
+}
+
+impl AuditWriter {
+    fn open(path: PathBuf, max_bytes: u64) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open audit log {:?}", path))?;
+        let written = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            file: BufWriter::new(file),
+            written,
+        })
+    }
+
+    fn run(mut self, queue: mpsc::Receiver<AuditRecord>) {
+        while let Ok(record) = queue.recv() {
+            self.write(&record);
+            // Batch whatever else is already queued into the same flush
+            while let Ok(record) = queue.try_recv() {
+                self.write(&record);
+            }
+            if let Err(e) = self.file.flush() {
+                tracing::warn!("Failed to flush audit log {:?}: {}", self.path, e);
+            }
+        }
+    }
+
+    fn write(&mut self, record: &AuditRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("Failed to serialize audit record for {}: {}", record.message_id, e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        if self.written > 0
+            && self.written + line.len() as u64 > self.max_bytes
+            && let Err(e) = self.rotate()
+        {
+            tracing::warn!("Failed to rotate audit log {:?}: {:#}", self.path, e);
+        }
+
+        match self.file.write_all(&line) {
+            Ok(()) => self.written += line.len() as u64,
+            Err(e) => tracing::warn!("Failed to write audit log {:?}: {}", self.path, e),
+        }
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1` (dropping the oldest), move the
+    /// current file to `<path>.1` and start a new one
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        for index in (1..ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        *self = Self::open(self.path.clone(), self.max_bytes)?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AuditEvent;
+    use std::time::SystemTime;
+
+    fn record(message_id: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: Some(SystemTime::now()),
+            event: AuditEvent::Forwarded,
+            message_id: message_id.to_string(),
+            from: "gateway-a".to_string(),
+            to: "gateway-c".to_string(),
+            route: vec!["gateway-a".to_string(), "gateway-b".to_string()],
+            status: "delivered".to_string(),
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_log_rotates_by_size() {
+        let dir = std::env::temp_dir().join(format!("mesh-gateway-audit-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+
+        let line_len = serde_json::to_vec(&record("m0")).unwrap().len() as u64 + 1;
+        let mut writer = AuditWriter::open(path.clone(), line_len * 2).unwrap();
+        for i in 0..5 {
+            writer.write(&record(&format!("m{}", i)));
+        }
+        writer.file.flush().unwrap();
+
+        // Two records per file, newest last
+        let lines = |path: &Path| fs::read_to_string(path).unwrap().lines().count();
+        assert_eq!(lines(&path), 1);
+        assert_eq!(lines(&rotated_path(&path, 1)), 2);
+        assert_eq!(lines(&rotated_path(&path, 2)), 2);
+        assert!(fs::read_to_string(&path).unwrap().contains("\"m4\""));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
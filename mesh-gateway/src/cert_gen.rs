@@ -0,0 +1,369 @@
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, SanType, PKCS_ECDSA_P256_SHA256};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Nodes to issue certificates for when none are named
+pub const DEFAULT_NODES: &[&str] = &["gateway-a", "gateway-b", "gateway-c"];
+
+/// Command-line arguments for certificate generation, shared by the
+/// `gen-certs` subcommand and the `gen_certs` binary
+#[derive(clap::Args, Debug)]
+pub struct GenCertsArgs {
+    /// Node to issue a certificate for (repeatable; default: gateway-a, gateway-b, gateway-c)
+    #[arg(long = "node", value_name = "NODE_ID")]
+    pub nodes: Vec<String>,
+
+    /// Extra SAN (IP address or DNS name) for every node certificate (repeatable)
+    #[arg(long = "san", value_name = "NAME_OR_IP")]
+    pub sans: Vec<String>,
+
+    /// Also add this host's hostname and non-loopback interface addresses as SANs
+    #[arg(long)]
+    pub auto_san: bool,
+
+    /// Replace an existing certs/ca.crt and certs/ca.key instead of reusing them
+    #[arg(long)]
+    pub new_ca: bool,
+}
+
+/// A certificate and its private key, PEM-encoded
+pub struct IssuedCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+}
+
+/// Generate a certificate per node into ./certs, signed by the Root CA there
+///
+/// An existing CA (certs/ca.crt and certs/ca.key) is reused, so certificates
+/// issued later, e.g. for a node added to the mesh, are trusted by the nodes
+/// already running; `new_ca` replaces it with a fresh one. `nodes` defaults to `DEFAULT_NODES` when empty. Every node certificate
+/// gets the `sans` entries (IP addresses or DNS names) on top of its node id
+/// and the loopback defaults. With `auto_san`, this host's hostname and
+/// non-loopback interface addresses are added as well.
+pub fn generate_certificates(
+    nodes: &[String],
+    sans: &[String],
+    auto_san: bool,
+    new_ca: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🔐 Generating certificates for mesh gateway network...\n");
+
+    let mut extra_sans: Vec<SanType> = sans.iter().map(|san| parse_san(san)).collect();
+    if !extra_sans.is_empty() {
+        println!("🏷️  Adding SANs: {}\n", describe_sans(&extra_sans));
+    }
+    if auto_san {
+        let sans = local_sans()?;
+        println!("🌐 Adding local SANs: {}\n", describe_sans(&sans));
+        for san in sans {
+            if !extra_sans.contains(&san) {
+                extra_sans.push(san);
+            }
+        }
+    }
+
+    // Create certs directory if it doesn't exist
+    let certs_dir = Path::new("certs");
+    fs::create_dir_all(certs_dir)?;
+
+    println!("1. Preparing Root CA...");
+    let (ca, reused) = load_or_generate_ca(certs_dir, new_ca)?;
+    if reused {
+        println!("   ✓ Reusing certs/ca.crt and certs/ca.key (pass --new-ca to replace them)\n");
+    } else {
+        println!("   ✓ Saved certs/ca.crt");
+        println!("   ✓ Saved certs/ca.key\n");
+    }
+
+    // Generate gateway certificates
+    let gateways: Vec<&str> = if nodes.is_empty() {
+        DEFAULT_NODES.to_vec()
+    } else {
+        nodes.iter().map(String::as_str).collect()
+    };
+
+    for &gateway_id in &gateways {
+        println!("2. Generating certificate for {}...", gateway_id);
+        let issued = generate_gateway_cert(gateway_id, &ca, &extra_sans)?;
+
+        let cert_file = format!("certs/{}.crt", gateway_id);
+        let key_file = format!("certs/{}.key", gateway_id);
+
+        fs::write(&cert_file, issued.cert_pem)?;
+        fs::write(&key_file, issued.key_pem)?;
+
+        println!("   ✓ Saved {}", cert_file);
+        println!("   ✓ Saved {}\n", key_file);
+    }
+
+    println!("✅ All certificates generated successfully!");
+    println!("\n📁 Certificate files created in ./certs/");
+    println!("   - ca.crt, ca.key (Root CA{})", if reused { ", unchanged" } else { "" });
+    for gateway_id in &gateways {
+        println!("   - {0}.crt, {0}.key", gateway_id);
+    }
+
+    Ok(())
+}
+
+/// Load the Root CA from `dir`/ca.crt and `dir`/ca.key, or generate and save
+/// a new one if they don't both exist (or `new_ca` is set)
+/// Returns the CA and whether the existing one was reused.
+fn load_or_generate_ca(dir: &Path, new_ca: bool) -> Result<(rcgen::Certificate, bool), Box<dyn std::error::Error>> {
+    let cert_path = dir.join("ca.crt");
+    let key_path = dir.join("ca.key");
+
+    if !new_ca && cert_path.exists() && key_path.exists() {
+        let key_pair = KeyPair::from_pem(&fs::read_to_string(&key_path)?)?;
+        let params = CertificateParams::from_ca_cert_pem(&fs::read_to_string(&cert_path)?, key_pair)?;
+        return Ok((rcgen::Certificate::from_params(params)?, true));
+    }
+
+    let ca = generate_ca()?;
+    fs::write(&cert_path, ca.serialize_pem()?)?;
+    fs::write(&key_path, ca.serialize_private_key_pem())?;
+    Ok((ca, false))
+}
+
+/// Generate a self-signed Root CA certificate
+fn generate_ca() -> Result<rcgen::Certificate, Box<dyn std::error::Error>> {
+    let mut params = CertificateParams::default();
+
+    // Set CA-specific parameters
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    params.key_usages = vec![
+        rcgen::KeyUsagePurpose::KeyCertSign,
+        rcgen::KeyUsagePurpose::CrlSign,
+    ];
+
+    // Set distinguished name
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, "MeshNet Root CA");
+    dn.push(DnType::OrganizationName, "Solitude Labs POC");
+    dn.push(DnType::CountryName, "US");
+    params.distinguished_name = dn;
+
+    // Set validity period (1 year)
+    params.not_before = time::OffsetDateTime::now_utc();
+    params.not_after = params.not_before + time::Duration::days(365);
+
+    // Generate the CA's key pair; gateway certificates are signed with it
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.key_pair = Some(KeyPair::generate(&PKCS_ECDSA_P256_SHA256)?);
+
+    // Generate certificate
+    let cert = rcgen::Certificate::from_params(params)?;
+
+    Ok(cert)
+}
+
+/// Generate a gateway certificate signed by the CA
+fn generate_gateway_cert(
+    gateway_id: &str,
+    ca: &rcgen::Certificate,
+    extra_sans: &[SanType],
+) -> Result<IssuedCert, Box<dyn std::error::Error>> {
+    let mut params = CertificateParams::default();
+
+    // Set distinguished name
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, gateway_id);
+    dn.push(DnType::OrganizationName, "Solitude Labs POC");
+    dn.push(DnType::OrganizationalUnitName, "Mesh Gateway");
+    params.distinguished_name = dn;
+
+    // Add subject alternative names (for TLS)
+    params.subject_alt_names = vec![
+        rcgen::SanType::DnsName(gateway_id.to_string()),
+        rcgen::SanType::DnsName("localhost".to_string()),
+        rcgen::SanType::IpAddress(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1))),
+    ];
+    for san in extra_sans {
+        if !params.subject_alt_names.contains(san) {
+            params.subject_alt_names.push(san.clone());
+        }
+    }
+
+    // Set key usage
+    params.key_usages = vec![
+        rcgen::KeyUsagePurpose::DigitalSignature,
+        rcgen::KeyUsagePurpose::KeyEncipherment,
+    ];
+
+    // Extended key usage (TLS server and client)
+    params.extended_key_usages = vec![
+        rcgen::ExtendedKeyUsagePurpose::ServerAuth,
+        rcgen::ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+
+    // Set validity period (1 year)
+    params.not_before = time::OffsetDateTime::now_utc();
+    params.not_after = params.not_before + time::Duration::days(365);
+
+    // Generate key pair and set algorithm
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.key_pair = Some(KeyPair::generate(&PKCS_ECDSA_P256_SHA256)?);
+
+    // Sign with the CA's key, naming the CA as issuer
+    let cert = rcgen::Certificate::from_params(params)?;
+
+    Ok(IssuedCert {
+        cert_pem: cert.serialize_pem_with_signer(ca)?,
+        key_pem: cert.serialize_private_key_pem(),
+    })
+}
+
+/// Collect this host's hostname and non-loopback interface addresses as SANs
+fn local_sans() -> Result<Vec<SanType>, Box<dyn std::error::Error>> {
+    let mut sans = Vec::new();
+
+    if let Some(name) = hostname::get()?.to_str()
+        && !name.is_empty()
+        && name != "localhost"
+    {
+        sans.push(SanType::DnsName(name.to_string()));
+    }
+
+    for interface in if_addrs::get_if_addrs()? {
+        let ip = interface.ip();
+        if is_routable_host_ip(&ip) && !sans.contains(&SanType::IpAddress(ip)) {
+            sans.push(SanType::IpAddress(ip));
+        }
+    }
+
+    Ok(sans)
+}
+
+/// Loopback addresses are already covered by the defaults, and IPv6 link-local
+/// addresses need a zone id to be dialed, so neither is useful in a cert
+fn is_routable_host_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_unspecified(),
+        IpAddr::V6(v6) => {
+            !v6.is_loopback() && !v6.is_unspecified() && (v6.segments()[0] & 0xffc0) != 0xfe80
+        }
+    }
+}
+
+/// A SAN entry from the command line: an IP address if it parses as one,
+/// otherwise a DNS name
+fn parse_san(value: &str) -> SanType {
+    match value.parse::<IpAddr>() {
+        Ok(ip) => SanType::IpAddress(ip),
+        Err(_) => SanType::DnsName(value.to_string()),
+    }
+}
+
+fn describe_sans(sans: &[SanType]) -> String {
+    if sans.is_empty() {
+        return "(none found)".to_string();
+    }
+
+    sans.iter()
+        .map(|san| match san {
+            SanType::DnsName(name) => name.clone(),
+            SanType::IpAddress(ip) => ip.to_string(),
+            other => format!("{:?}", other),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_sans_exclude_loopback() {
+        let sans = local_sans().unwrap();
+        for san in &sans {
+            if let SanType::IpAddress(ip) = san {
+                assert!(!ip.is_loopback(), "loopback address {} included", ip);
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_local_ipv6_not_routable() {
+        assert!(!is_routable_host_ip(&"fe80::1".parse().unwrap()));
+        assert!(!is_routable_host_ip(&"127.0.0.1".parse().unwrap()));
+        assert!(is_routable_host_ip(&"10.0.0.5".parse().unwrap()));
+        assert!(is_routable_host_ip(&"2001:db8::1".parse().unwrap()));
+    }
+
+    fn der(pem: &str) -> rustls::Certificate {
+        rustls::Certificate(rustls_pemfile::certs(&mut pem.as_bytes()).unwrap().remove(0))
+    }
+
+    /// The DNS names and IP addresses in a certificate's SANs
+    fn san_names(pem: &str) -> Vec<String> {
+        let cert = der(pem);
+        let (_, parsed) = x509_parser::parse_x509_certificate(&cert.0).unwrap();
+        parsed
+            .subject_alternative_name()
+            .unwrap()
+            .unwrap()
+            .value
+            .general_names
+            .iter()
+            .map(|name| match name {
+                x509_parser::extensions::GeneralName::DNSName(dns) => dns.to_string(),
+                x509_parser::extensions::GeneralName::IPAddress(bytes) => match bytes.len() {
+                    4 => IpAddr::from(<[u8; 4]>::try_from(*bytes).unwrap()).to_string(),
+                    _ => IpAddr::from(<[u8; 16]>::try_from(*bytes).unwrap()).to_string(),
+                },
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_command_line_sans_appear_in_cert() {
+        let ca = generate_ca().unwrap();
+        let extra: Vec<SanType> = ["gw-dc1.example.com", "10.0.0.5", "2001:db8::5"].iter().map(|s| parse_san(s)).collect();
+        let issued = generate_gateway_cert("gw-dc1", &ca, &extra).unwrap();
+        let names = san_names(&issued.cert_pem);
+
+        for expected in ["gw-dc1", "localhost", "127.0.0.1", "gw-dc1.example.com", "10.0.0.5", "2001:db8::5"] {
+            assert!(names.iter().any(|n| n == expected), "{} missing from {:?}", expected, names);
+        }
+    }
+
+    #[test]
+    fn test_extra_sans_added_to_gateway_cert() {
+        let ca = generate_ca().unwrap();
+        let extra = vec![SanType::IpAddress("10.0.0.5".parse().unwrap())];
+        let issued = generate_gateway_cert("gateway-a", &ca, &extra).unwrap();
+
+        let sans = san_names(&issued.cert_pem);
+        assert!(sans.contains(&"10.0.0.5".to_string()));
+        assert!(sans.contains(&"localhost".to_string()));
+    }
+
+    #[test]
+    fn test_gateway_cert_verifies_against_persisted_ca() {
+        let dir = std::env::temp_dir().join(format!("mesh-gateway-cert-gen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let (ca, reused) = load_or_generate_ca(&dir, false).unwrap();
+        assert!(!reused);
+        let first = generate_gateway_cert("gateway-a", &ca, &[]).unwrap();
+
+        // A later run reuses the saved CA key, so both certs chain to ca.crt
+        let (ca, reused) = load_or_generate_ca(&dir, false).unwrap();
+        assert!(reused);
+        let second = generate_gateway_cert("gateway-d", &ca, &[]).unwrap();
+
+        let roots = crate::certs::load_ca_cert(dir.join("ca.crt")).unwrap();
+        crate::certs::verify_cert_chain(&[der(&first.cert_pem)], roots.clone()).unwrap();
+        crate::certs::verify_cert_chain(&[der(&second.cert_pem)], roots).unwrap();
+
+        // A new CA doesn't vouch for certificates issued by the old one
+        load_or_generate_ca(&dir, true).unwrap();
+        let roots = crate::certs::load_ca_cert(dir.join("ca.crt")).unwrap();
+        assert!(crate::certs::verify_cert_chain(&[der(&first.cert_pem)], roots).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// What a file looked like when last checked: its modification time and
+/// length, or `None` if it couldn't be read
+type Fingerprint = Option<(SystemTime, u64)>;
+
+/// Notices when any of a set of files is modified, replaced or removed
+///
+/// Files are polled rather than watched through the OS, so a replacement by
+/// rename (how most tools rotate certificates) is caught the same way as an
+/// in-place rewrite.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Fingerprint)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            files: paths
+                .into_iter()
+                .map(|path| {
+                    let fingerprint = fingerprint(&path);
+                    (path, fingerprint)
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether any file differs from the last check
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, seen) in &mut self.files {
+            let current = fingerprint(path);
+            if current != *seen {
+                *seen = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Spawns a background task that calls `on_change` after any of `paths`
+/// changes
+///
+/// The callback runs once the files have stopped changing for one `period`,
+/// so a certificate and key rotated one after the other are picked up
+/// together instead of being tried as a mismatched pair in between.
+pub fn spawn_file_watch_task<F>(paths: Vec<PathBuf>, period: Duration, on_change: F) -> JoinHandle<()>
+where
+    F: Fn() + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut watcher = FileWatcher::new(paths);
+        let mut interval = time::interval(period);
+        interval.tick().await;
+        let mut pending = false;
+
+        loop {
+            interval.tick().await;
+
+            if watcher.changed() {
+                pending = true;
+            } else if pending {
+                pending = false;
+                on_change();
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestPki;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_replacing_cert_triggers_callback() {
+        let pki = TestPki::generate(&["gateway-b", "gateway-c"]);
+        let cert_path = pki.cert_path("gateway-b");
+
+        let (changes, mut changed) = mpsc::unbounded_channel();
+        let task = spawn_file_watch_task(vec![cert_path.clone(), pki.key_path("gateway-b")], Duration::from_millis(20), move || {
+            let _ = changes.send(());
+        });
+
+        // Nothing has changed yet
+        assert!(time::timeout(Duration::from_millis(100), changed.recv()).await.is_err());
+
+        // Replace the certificate the way rotation tools do: write aside, then rename over
+        let staged = cert_path.with_extension("crt.new");
+        fs::copy(pki.cert_path("gateway-c"), &staged).unwrap();
+        fs::rename(&staged, &cert_path).unwrap();
+
+        time::timeout(Duration::from_secs(2), changed.recv())
+            .await
+            .expect("replacing the certificate should trigger a reload")
+            .unwrap();
+
+        // One replacement, one callback
+        assert!(time::timeout(Duration::from_millis(100), changed.recv()).await.is_err());
+        task.abort();
+    }
+}
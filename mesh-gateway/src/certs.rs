@@ -1,11 +1,21 @@
 use anyhow::{Context, Result};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P384_SHA384_ASN1_SIGNING,
+};
+use rustls::server::{AllowAnyAuthenticatedClient, ClientCertVerifier};
 use rustls::{Certificate, PrivateKey, RootCertStore};
-use rustls_pemfile::{certs, pkcs8_private_keys};
+use rustls_pemfile::{certs, ec_private_keys, pkcs8_private_keys, rsa_private_keys};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use x509_parser::prelude::{FromDer, X509Certificate};
 
 /// Loads a certificate from a PEM file
+///
+/// A file holding a chain is returned leaf first, each certificate followed
+/// by its issuer, as TLS requires; a chain in another order is reordered.
 pub fn load_cert(path: impl AsRef<Path>) -> Result<Vec<Certificate>> {
     let file = File::open(path.as_ref())
         .context(format!("Failed to open certificate file: {:?}", path.as_ref()))?;
@@ -21,17 +31,133 @@ pub fn load_cert(path: impl AsRef<Path>) -> Result<Vec<Certificate>> {
         anyhow::bail!("No certificates found in file: {:?}", path.as_ref());
     }
 
-    Ok(certs)
+    let ordered = order_cert_chain(&certs).with_context(|| format!("Invalid certificate chain in {:?}", path.as_ref()))?;
+    if ordered != certs {
+        tracing::warn!("Certificates in {:?} are not leaf first; using them reordered", path.as_ref());
+    }
+
+    Ok(ordered)
+}
+
+/// Orders a certificate chain leaf first, each certificate followed by the
+/// one named as its issuer
+///
+/// The leaf is the only certificate that issued none of the others. A
+/// certificate that isn't on the leaf's issuer path is an error.
+pub fn order_cert_chain(certs: &[Certificate]) -> Result<Vec<Certificate>> {
+    if certs.len() < 2 {
+        return Ok(certs.to_vec());
+    }
+
+    let parsed = certs
+        .iter()
+        .map(|cert| {
+            X509Certificate::from_der(&cert.0)
+                .map(|(_, cert)| cert)
+                .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let subject = |i: usize| parsed[i].subject().as_raw();
+    let issuer = |i: usize| parsed[i].issuer().as_raw();
+    let count = certs.len();
+
+    let leaves: Vec<usize> = (0..count)
+        .filter(|&i| !(0..count).any(|j| j != i && issuer(j) == subject(i)))
+        .collect();
+    let [leaf] = leaves[..] else {
+        anyhow::bail!(
+            "expected one leaf certificate followed by its issuers, but {} of the {} certificates issued none of the others",
+            leaves.len(),
+            count
+        );
+    };
+
+    let mut order = vec![leaf];
+    while let Some(&current) = order.last()
+        && issuer(current) != subject(current)
+        && let Some(next) = (0..count).find(|j| !order.contains(j) && subject(*j) == issuer(current))
+    {
+        order.push(next);
+    }
+
+    if let Some(stray) = (0..count).find(|i| !order.contains(i)) {
+        anyhow::bail!(
+            "'{}' is not an issuer of '{}' (each certificate after the leaf must have issued the one before it)",
+            parsed[stray].subject(),
+            parsed[leaf].subject()
+        );
+    }
+
+    Ok(order.into_iter().map(|i| certs[i].clone()).collect())
+}
+
+/// Warn when a certificate expires within this many days
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// Checks that a certificate is currently within its validity period
+///
+/// An expired or not-yet-valid certificate is an error, since every mTLS
+/// handshake with it would fail. One that expires within 14 days only
+/// logs a warning.
+pub fn check_cert_validity(cert: &Certificate) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+
+    if let Some(remaining) = check_cert_validity_at(cert, now)? {
+        tracing::warn!(
+            "⚠️  Certificate expires in {} day(s), {} hour(s); renew it soon",
+            remaining / 86_400,
+            remaining % 86_400 / 3_600
+        );
+    }
+
+    Ok(())
+}
+
+/// Validity check against `now` (UNIX seconds)
+/// Returns the seconds left when the certificate is close to expiring.
+fn check_cert_validity_at(cert: &Certificate, now: i64) -> Result<Option<i64>> {
+    let (_, parsed) = X509Certificate::from_der(&cert.0)
+        .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+    let validity = parsed.validity();
+    let subject = parsed.subject();
+
+    if now < validity.not_before.timestamp() {
+        anyhow::bail!("Certificate for {} is not valid until {}", subject, validity.not_before);
+    }
+
+    if now > validity.not_after.timestamp() {
+        anyhow::bail!("Certificate for {} expired at {}", subject, validity.not_after);
+    }
+
+    let remaining = validity.not_after.timestamp() - now;
+    Ok((remaining < EXPIRY_WARNING_DAYS * 86_400).then_some(remaining))
+}
+
+/// The first CommonName in a certificate's subject, if it has one
+/// Gateway certificates carry their node id here.
+pub fn cert_common_name(cert: &Certificate) -> Option<String> {
+    let (_, parsed) = X509Certificate::from_der(&cert.0).ok()?;
+    let common_name = parsed.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(common_name.to_string())
 }
 
 /// Loads a private key from a PEM file
+/// Accepts PKCS#8, RSA (PKCS#1) and EC (SEC1) keys, in that order of preference.
 pub fn load_private_key(path: impl AsRef<Path>) -> Result<PrivateKey> {
-    let file = File::open(path.as_ref())
+    let mut file = File::open(path.as_ref())
         .context(format!("Failed to open private key file: {:?}", path.as_ref()))?;
 
-    let mut reader = BufReader::new(file);
+    let mut pem = Vec::new();
+    file.read_to_end(&mut pem)
+        .context(format!("Failed to read private key file: {:?}", path.as_ref()))?;
 
-    let mut keys = pkcs8_private_keys(&mut reader)?;
+    let mut keys = pkcs8_private_keys(&mut pem.as_slice())?;
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut pem.as_slice())?;
+    }
+    if keys.is_empty() {
+        keys = ec_private_keys(&mut pem.as_slice())?;
+    }
 
     if keys.is_empty() {
         anyhow::bail!("No private keys found in file: {:?}", path.as_ref());
@@ -46,23 +172,15 @@ pub fn load_private_key(path: impl AsRef<Path>) -> Result<PrivateKey> {
 
 /// Loads the Root CA certificate into a RootCertStore
 pub fn load_ca_cert(path: impl AsRef<Path>) -> Result<RootCertStore> {
-    let file = File::open(path.as_ref())
-        .context(format!("Failed to open CA certificate file: {:?}", path.as_ref()))?;
-
-    let mut reader = BufReader::new(file);
+    load_ca_certs(&[path])
+}
 
+/// Loads the CA certificates from several PEM files into one RootCertStore
+/// Each file may itself be a bundle, e.g. the old and new CA during a rotation.
+pub fn load_ca_certs(paths: &[impl AsRef<Path>]) -> Result<RootCertStore> {
     let mut root_store = RootCertStore::empty();
 
-    let certs: Vec<Certificate> = certs(&mut reader)?
-        .into_iter()
-        .map(Certificate)
-        .collect();
-
-    if certs.is_empty() {
-        anyhow::bail!("No CA certificates found in file: {:?}", path.as_ref());
-    }
-
-    for cert in certs {
+    for cert in load_ca_bundle(paths)? {
         root_store.add(&cert)
             .context("Failed to add CA certificate to root store")?;
     }
@@ -70,6 +188,125 @@ pub fn load_ca_cert(path: impl AsRef<Path>) -> Result<RootCertStore> {
     Ok(root_store)
 }
 
+/// Reads every certificate from the given CA PEM files
+/// Each file must contain at least one certificate.
+pub fn load_ca_bundle(paths: &[impl AsRef<Path>]) -> Result<Vec<Certificate>> {
+    let mut bundle = Vec::new();
+
+    for path in paths {
+        let file = File::open(path.as_ref())
+            .context(format!("Failed to open CA certificate file: {:?}", path.as_ref()))?;
+
+        let mut reader = BufReader::new(file);
+
+        let certs: Vec<Certificate> = certs(&mut reader)?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+
+        if certs.is_empty() {
+            anyhow::bail!("No CA certificates found in file: {:?}", path.as_ref());
+        }
+
+        bundle.extend(certs);
+    }
+
+    Ok(bundle)
+}
+
+/// Checks that a private key belongs to a certificate by comparing public keys
+/// Supports the ECDSA (P-256/P-384), RSA and Ed25519 keys rustls can serve, in
+/// the same PKCS#8, PKCS#1 and SEC1 encodings `load_private_key` accepts.
+pub fn verify_key_matches_cert(cert: &Certificate, key: &PrivateKey) -> Result<()> {
+    let (_, parsed) = X509Certificate::from_der(&cert.0)
+        .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+    let cert_public_key = parsed.public_key().subject_public_key.data.as_ref();
+
+    let rng = SystemRandom::new();
+    let key_public_key: Vec<u8> = if let Ok(pair) = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &key.0, &rng) {
+        pair.public_key().as_ref().to_vec()
+    } else if let Ok(pair) = EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, &key.0, &rng) {
+        pair.public_key().as_ref().to_vec()
+    } else if let Ok(pair) =
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &sec1_to_pkcs8(PKCS8_PREFIX_P256, &key.0), &rng)
+    {
+        pair.public_key().as_ref().to_vec()
+    } else if let Ok(pair) =
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, &sec1_to_pkcs8(PKCS8_PREFIX_P384, &key.0), &rng)
+    {
+        pair.public_key().as_ref().to_vec()
+    } else if let Ok(pair) = RsaKeyPair::from_pkcs8(&key.0).or_else(|_| RsaKeyPair::from_der(&key.0)) {
+        pair.public_key().as_ref().to_vec()
+    } else if let Ok(pair) = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&key.0) {
+        pair.public_key().as_ref().to_vec()
+    } else {
+        anyhow::bail!("Unsupported or malformed private key");
+    };
+
+    if key_public_key != cert_public_key {
+        anyhow::bail!("Private key does not match the certificate's public key");
+    }
+
+    Ok(())
+}
+
+/// PKCS#8 fields preceding the key for EC keys: version 0 and the
+/// id-ecPublicKey algorithm with the named curve
+const PKCS8_PREFIX_P256: &[u8] = &[
+    0x02, 0x01, 0x00, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a, 0x86,
+    0x48, 0xce, 0x3d, 0x03, 0x01, 0x07,
+];
+const PKCS8_PREFIX_P384: &[u8] = &[
+    0x02, 0x01, 0x00, 0x30, 0x10, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x05, 0x2b, 0x81,
+    0x04, 0x00, 0x22,
+];
+
+/// Wrap a SEC1 EC private key in a PKCS#8 envelope so ring can parse it
+fn sec1_to_pkcs8(prefix: &[u8], sec1: &[u8]) -> Vec<u8> {
+    let mut body = prefix.to_vec();
+    body.extend(der_tlv(0x04, sec1));
+    der_tlv(0x30, &body)
+}
+
+/// Encode a DER tag-length-value
+fn der_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | len_bytes.len() as u8);
+        out.extend(len_bytes);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+/// Checks that a certificate chain is valid and issued by one of the given roots
+pub fn verify_cert_chain(chain: &[Certificate], roots: RootCertStore) -> Result<()> {
+    let Some((end_entity, intermediates)) = chain.split_first() else {
+        anyhow::bail!("Certificate chain is empty");
+    };
+
+    AllowAnyAuthenticatedClient::new(roots)
+        .verify_client_cert(end_entity, intermediates, SystemTime::now())
+        .map_err(|e| match e {
+            rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownIssuer) => {
+                let top = chain.last().and_then(|cert| {
+                    X509Certificate::from_der(&cert.0).ok().map(|(_, cert)| cert.issuer().to_string())
+                });
+                anyhow::anyhow!(
+                    "Certificate does not chain to the CA: its issuer '{}' is not a trusted CA; if that is an intermediate CA, append its certificate to the certificate file",
+                    top.unwrap_or_default()
+                )
+            }
+            e => anyhow::anyhow!("Certificate does not chain to the CA: {}", e),
+        })?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,11 +317,34 @@ mod tests {
         assert!(ca_cert.is_ok(), "Failed to load CA certificate");
     }
 
+    #[test]
+    fn test_load_ca_bundle_and_multiple_files() {
+        let old_ca = crate::test_util::TestPki::generate(&[]);
+        let new_ca = crate::test_util::TestPki::generate(&["gateway-a"]);
+
+        let bundle_path = new_ca.cert_path("bundle");
+        let mut bundle = std::fs::read(old_ca.ca_path()).unwrap();
+        bundle.extend(std::fs::read(new_ca.ca_path()).unwrap());
+        std::fs::write(&bundle_path, bundle).unwrap();
+
+        let store = load_ca_cert(&bundle_path).unwrap();
+        assert_eq!(store.len(), 2);
+
+        let store = load_ca_certs(&[old_ca.ca_path(), new_ca.ca_path()]).unwrap();
+        assert_eq!(store.len(), 2);
+
+        // A gateway cert issued by the new CA verifies against the combined store
+        let chain = load_cert(new_ca.cert_path("gateway-a")).unwrap();
+        assert!(verify_cert_chain(&chain, store).is_ok());
+    }
+
     #[test]
     fn test_load_gateway_cert() {
         let cert = load_cert("certs/gateway-a.crt");
         assert!(cert.is_ok(), "Failed to load gateway certificate");
-        assert!(!cert.unwrap().is_empty(), "Certificate list is empty");
+        let cert = cert.unwrap();
+        assert!(!cert.is_empty(), "Certificate list is empty");
+        assert_eq!(cert_common_name(&cert[0]).as_deref(), Some("gateway-a"));
     }
 
     #[test]
@@ -92,4 +352,106 @@ mod tests {
         let key = load_private_key("certs/gateway-a.key");
         assert!(key.is_ok(), "Failed to load private key");
     }
+
+    #[test]
+    fn test_load_rsa_pkcs1_private_key() {
+        let key = load_private_key("testdata/rsa-pkcs1.key").unwrap();
+        assert!(rustls::sign::any_supported_type(&key).is_ok());
+
+        let cert = load_cert("testdata/rsa-pkcs1.crt").unwrap().remove(0);
+        assert!(verify_key_matches_cert(&cert, &key).is_ok());
+    }
+
+    #[test]
+    fn test_load_ec_sec1_private_key() {
+        let key = load_private_key("testdata/ec-sec1.key").unwrap();
+        assert!(rustls::sign::any_supported_type(&key).is_ok());
+
+        let cert = load_cert("testdata/ec-sec1.crt").unwrap().remove(0);
+        assert!(verify_key_matches_cert(&cert, &key).is_ok());
+    }
+
+    /// Self-signed certificate valid between the given dates
+    fn cert_valid_between(not_before: (i32, u8, u8), not_after: (i32, u8, u8)) -> Certificate {
+        let mut params = rcgen::CertificateParams::new(vec!["gateway-a".to_string()]);
+        params.not_before = rcgen::date_time_ymd(not_before.0, not_before.1, not_before.2);
+        params.not_after = rcgen::date_time_ymd(not_after.0, not_after.1, not_after.2);
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+        Certificate(cert.serialize_der().unwrap())
+    }
+
+    #[test]
+    fn test_expired_cert_is_rejected() {
+        let expired = cert_valid_between((2019, 1, 1), (2020, 1, 1));
+        let err = check_cert_validity(&expired).unwrap_err();
+        assert!(err.to_string().contains("expired"), "{}", err);
+
+        let not_yet_valid = cert_valid_between((2999, 1, 1), (3000, 1, 1));
+        assert!(check_cert_validity(&not_yet_valid).is_err());
+    }
+
+    #[test]
+    fn test_cert_expiring_soon_is_flagged() {
+        let cert = cert_valid_between((2020, 1, 1), (2030, 1, 1));
+        // 2030-01-01T00:00:00Z
+        let expiry = 1_893_456_000;
+
+        assert_eq!(check_cert_validity_at(&cert, expiry - 86_400).unwrap(), Some(86_400));
+        assert_eq!(check_cert_validity_at(&cert, expiry - 30 * 86_400).unwrap(), None);
+    }
+
+    #[test]
+    fn test_verify_key_matches_cert() {
+        let pki = crate::test_util::TestPki::generate(&["gateway-a", "gateway-b"]);
+        let cert = load_cert(pki.cert_path("gateway-a")).unwrap().remove(0);
+
+        assert!(verify_key_matches_cert(&cert, &load_private_key(pki.key_path("gateway-a")).unwrap()).is_ok());
+        assert!(verify_key_matches_cert(&cert, &load_private_key(pki.key_path("gateway-b")).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_verify_cert_chain() {
+        let pki = crate::test_util::TestPki::generate(&["gateway-a"]);
+        let other = crate::test_util::TestPki::generate(&[]);
+        let chain = load_cert(pki.cert_path("gateway-a")).unwrap();
+
+        assert!(verify_cert_chain(&chain, load_ca_cert(pki.ca_path()).unwrap()).is_ok());
+        assert!(verify_cert_chain(&chain, load_ca_cert(other.ca_path()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_chain_with_intermediate_is_ordered_leaf_first() {
+        let pki = crate::test_util::TestPki::generate_with_intermediate(&["gateway-a"]);
+        let leaf = std::fs::read(pki.cert_path("gateway-a")).unwrap();
+        let intermediate = std::fs::read(pki.intermediate_path()).unwrap();
+        let roots = || load_ca_cert(pki.ca_path()).unwrap();
+
+        // Correct order
+        let path = pki.cert_path("ordered");
+        std::fs::write(&path, [leaf.clone(), intermediate.clone()].concat()).unwrap();
+        let ordered = load_cert(&path).unwrap();
+        assert_eq!(cert_common_name(&ordered[0]).as_deref(), Some("gateway-a"));
+        assert!(verify_cert_chain(&ordered, roots()).is_ok());
+
+        // Intermediate first: reordered to the same chain
+        let path = pki.cert_path("reversed");
+        std::fs::write(&path, [intermediate, leaf].concat()).unwrap();
+        assert_eq!(load_cert(&path).unwrap(), ordered);
+
+        // Without the intermediate, the error says what's missing
+        let leaf_only = load_cert(pki.cert_path("gateway-a")).unwrap();
+        let err = verify_cert_chain(&leaf_only, roots()).unwrap_err().to_string();
+        assert!(err.contains("Test Intermediate CA") && err.contains("intermediate"), "{}", err);
+    }
+
+    #[test]
+    fn test_unrelated_certificates_in_chain_rejected() {
+        let pki = crate::test_util::TestPki::generate(&["gateway-a", "gateway-b"]);
+        let path = pki.cert_path("mixed");
+        let contents = [std::fs::read(pki.cert_path("gateway-a")).unwrap(), std::fs::read(pki.cert_path("gateway-b")).unwrap()].concat();
+        std::fs::write(&path, contents).unwrap();
+
+        let err = load_cert(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("leaf certificate"), "{:#}", err);
+    }
 }
@@ -0,0 +1,188 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// When a peer's circuit opens, and for how long
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakerPolicy {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// Failures older than this no longer count towards the threshold
+    pub window: Duration,
+    /// How long an open circuit fails fast before letting a probe through
+    pub cooldown: Duration,
+}
+
+/// Per-peer circuit breakers for forwarding
+///
+/// After `failure_threshold` consecutive failures to a peer within `window`,
+/// its circuit opens and forwards to it fail fast. Once `cooldown` has
+/// passed, one forward is let through as a probe (half-open): success closes
+/// the circuit, failure opens it for another cooldown.
+#[derive(Clone, Default)]
+pub struct CircuitBreakers {
+    circuits: Arc<Mutex<HashMap<String, Circuit>>>,
+}
+
+#[derive(Default)]
+struct Circuit {
+    /// Consecutive failures within the window, oldest first
+    failures: VecDeque<Instant>,
+    /// When the circuit last opened; None while it is closed
+    opened_at: Option<Instant>,
+    /// When the half-open probe was let through, while it is outstanding
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreakers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a forward to `peer` may be attempted now
+    pub fn allow(&self, peer: &str, policy: &BreakerPolicy) -> bool {
+        self.allow_at(peer, policy, Instant::now())
+    }
+
+    fn allow_at(&self, peer: &str, policy: &BreakerPolicy, now: Instant) -> bool {
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(peer) else {
+            return true;
+        };
+        let Some(opened_at) = circuit.opened_at else {
+            return true;
+        };
+        if now.saturating_duration_since(opened_at) < policy.cooldown {
+            return false;
+        }
+
+        // Half-open: one probe at a time, or another if the last one never reported back
+        if circuit
+            .probe_started
+            .is_some_and(|started| now.saturating_duration_since(started) < policy.cooldown)
+        {
+            return false;
+        }
+        circuit.probe_started = Some(now);
+        true
+    }
+
+    /// Record a successful forward to `peer`, closing its circuit
+    pub fn record_success(&self, peer: &str) {
+        let closed = self.circuits.lock().unwrap().remove(peer);
+        if closed.is_some_and(|circuit| circuit.opened_at.is_some()) {
+            tracing::info!("Circuit to {} closed", peer);
+        }
+    }
+
+    /// Record a failed forward to `peer`; returns true if it opened the circuit
+    pub fn record_failure(&self, peer: &str, policy: &BreakerPolicy) -> bool {
+        self.record_failure_at(peer, policy, Instant::now())
+    }
+
+    fn record_failure_at(&self, peer: &str, policy: &BreakerPolicy, now: Instant) -> bool {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(peer.to_string()).or_default();
+
+        if circuit.opened_at.is_some() {
+            // Only the probe's failure re-opens the circuit; forwards started
+            // before it opened may still be failing
+            if circuit.probe_started.take().is_none() {
+                return false;
+            }
+            circuit.opened_at = Some(now);
+            tracing::warn!("Probe to {} failed, circuit open for another {:?}", peer, policy.cooldown);
+            return true;
+        }
+
+        circuit.failures.push_back(now);
+        while circuit
+            .failures
+            .front()
+            .is_some_and(|&failed| now.saturating_duration_since(failed) > policy.window)
+        {
+            circuit.failures.pop_front();
+        }
+        if circuit.failures.len() < policy.failure_threshold as usize {
+            return false;
+        }
+
+        circuit.failures.clear();
+        circuit.opened_at = Some(now);
+        tracing::warn!(
+            "{} consecutive forwards to {} failed, circuit open for {:?}",
+            policy.failure_threshold,
+            peer,
+            policy.cooldown
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: BreakerPolicy = BreakerPolicy {
+        failure_threshold: 3,
+        window: Duration::from_secs(10),
+        cooldown: Duration::from_secs(30),
+    };
+
+    #[test]
+    fn test_consecutive_failures_trip_breaker() {
+        let breakers = CircuitBreakers::new();
+        let start = Instant::now();
+
+        assert!(!breakers.record_failure_at("gateway-b", &POLICY, start));
+        assert!(!breakers.record_failure_at("gateway-b", &POLICY, start + Duration::from_secs(1)));
+        assert!(breakers.allow_at("gateway-b", &POLICY, start + Duration::from_secs(1)));
+        assert!(breakers.record_failure_at("gateway-b", &POLICY, start + Duration::from_secs(2)));
+
+        // Open: fail fast, for this peer only
+        assert!(!breakers.allow_at("gateway-b", &POLICY, start + Duration::from_secs(3)));
+        assert!(breakers.allow_at("gateway-c", &POLICY, start + Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_scattered_or_interrupted_failures_do_not_trip() {
+        let breakers = CircuitBreakers::new();
+        let start = Instant::now();
+
+        // Spread wider than the window
+        for secs in [0, 8, 16, 24] {
+            assert!(!breakers.record_failure_at("gateway-b", &POLICY, start + Duration::from_secs(secs)));
+        }
+
+        // Broken up by a success
+        let later = start + Duration::from_secs(60);
+        breakers.record_failure_at("gateway-b", &POLICY, later);
+        breakers.record_failure_at("gateway-b", &POLICY, later);
+        breakers.record_success("gateway-b");
+        assert!(!breakers.record_failure_at("gateway-b", &POLICY, later));
+        assert!(breakers.allow_at("gateway-b", &POLICY, later));
+    }
+
+    #[test]
+    fn test_breaker_recovers_after_cooldown_probe() {
+        let breakers = CircuitBreakers::new();
+        let start = Instant::now();
+        for _ in 0..3 {
+            breakers.record_failure_at("gateway-b", &POLICY, start);
+        }
+        let cooled = start + POLICY.cooldown;
+
+        // Half-open: a single probe goes through; it fails, so the circuit re-opens
+        assert!(breakers.allow_at("gateway-b", &POLICY, cooled));
+        assert!(!breakers.allow_at("gateway-b", &POLICY, cooled));
+        assert!(breakers.record_failure_at("gateway-b", &POLICY, cooled));
+        assert!(!breakers.allow_at("gateway-b", &POLICY, cooled + Duration::from_secs(1)));
+
+        // The next probe succeeds and closes it
+        let recovered = cooled + POLICY.cooldown;
+        assert!(breakers.allow_at("gateway-b", &POLICY, recovered));
+        breakers.record_success("gateway-b");
+        assert!(breakers.allow_at("gateway-b", &POLICY, recovered));
+        assert!(breakers.allow_at("gateway-b", &POLICY, recovered));
+    }
+}
@@ -1,44 +1,245 @@
+use crate::certs::{load_ca_bundle, load_cert, load_private_key};
+use crate::config::{PeerNameVerification, PeerProtocol};
+use crate::dscp::{self, DscpClass, MarkedClient};
 use anyhow::{Context, Result};
-use reqwest::{Client, Identity};
-use std::fs;
+use reqwest::Client;
+use rustls::client::{
+    verify_server_cert_signed_by_trust_anchor, ServerCertVerified, ServerCertVerifier,
+    WebPkiVerifier,
+};
+use rustls::server::ParsedCertificate;
+use rustls::{Certificate, ClientConfig, KeyLog, RootCertStore, ServerName};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Timeouts applied to every request made by an mTLS client
+#[derive(Debug, Clone, Copy)]
+pub struct ClientTimeouts {
+    /// Time allowed to establish a connection, including the TLS handshake
+    pub connect: Duration,
+    /// Time allowed for a whole request, from connecting to reading the response
+    pub request: Duration,
+}
+
+impl Default for ClientTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(5),
+            request: Duration::from_secs(10),
+        }
+    }
+}
 
 /// Creates an HTTPS client configured for mTLS
 pub fn create_mtls_client(
     cert_path: impl AsRef<Path>,
     key_path: impl AsRef<Path>,
-    ca_cert_path: impl AsRef<Path>,
+    ca_cert_paths: &[impl AsRef<Path>],
+    timeouts: ClientTimeouts,
+    name_verification: PeerNameVerification,
 ) -> Result<Client> {
-    // Load client certificate and key
-    let cert_pem = fs::read(cert_path.as_ref())
-        .context(format!("Failed to read certificate: {:?}", cert_path.as_ref()))?;
+    let mut config = mtls_rustls_config(cert_path, key_path, ca_cert_paths, name_verification)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
 
-    let key_pem = fs::read(key_path.as_ref())
-        .context(format!("Failed to read private key: {:?}", key_path.as_ref()))?;
+    Client::builder()
+        .use_preconfigured_tls(config)
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.request)
+        .build()
+        .context("Failed to build HTTPS client")
+}
 
-    // Combine cert and key for reqwest Identity
-    let mut pem = cert_pem;
-    pem.extend_from_slice(&key_pem);
+/// How often the peer clients opened a connection versus reused a pooled one
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionStats {
+    /// TLS handshakes completed, i.e. connections opened
+    handshakes: Arc<AtomicU64>,
+    /// Requests sent, over a new or a pooled connection
+    requests: Arc<AtomicU64>,
+}
 
-    let identity = Identity::from_pem(&pem)
-        .context("Failed to create identity from certificate and key")?;
+impl ConnectionStats {
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
 
-    // Load CA certificate
-    let ca_cert = fs::read(ca_cert_path.as_ref())
-        .context(format!("Failed to read CA certificate: {:?}", ca_cert_path.as_ref()))?;
+    pub fn handshakes(&self) -> u64 {
+        self.handshakes.load(Ordering::Relaxed)
+    }
 
-    let ca_cert = reqwest::Certificate::from_pem(&ca_cert)
-        .context("Failed to parse CA certificate")?;
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
 
-    // Build the client with mTLS configuration
-    let client = Client::builder()
-        .identity(identity)
-        .add_root_certificate(ca_cert)
-        .use_rustls_tls()
-        .build()
-        .context("Failed to build HTTPS client")?;
+    /// Requests that went over an already open connection
+    pub fn reused(&self) -> u64 {
+        self.requests().saturating_sub(self.handshakes())
+    }
+}
+
+/// Counts handshakes as rustls reports each one's secrets
+///
+/// Every handshake logs exactly one `CLIENT_RANDOM` (TLS 1.2) or
+/// `CLIENT_TRAFFIC_SECRET_0` (TLS 1.3); nothing is written anywhere.
+struct HandshakeCounter(Arc<AtomicU64>);
+
+impl KeyLog for HandshakeCounter {
+    fn log(&self, label: &str, _client_random: &[u8], _secret: &[u8]) {
+        if label == "CLIENT_RANDOM" || label == "CLIENT_TRAFFIC_SECRET_0" {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Pre-configured mTLS clients, one per peer protocol preference
+///
+/// With DSCP classes configured there is also a marked pair per DSCP value,
+/// used for messages whose priority falls in a class.
+#[derive(Clone)]
+pub struct PeerClients {
+    h2: Client,
+    http1: Client,
+    dscp_classes: Vec<DscpClass>,
+    /// Map of DSCP value -> (h2-capable, HTTP/1.1-only) marked clients
+    marked: HashMap<u8, (MarkedClient, MarkedClient)>,
+    stats: ConnectionStats,
+}
+
+impl PeerClients {
+    /// Build an h2-capable client and an HTTP/1.1-only client sharing the same
+    /// identity, plus a marked pair for each DSCP value in `dscp_classes`
+    ///
+    /// All count their TLS handshakes into `stats()`.
+    pub fn new(
+        cert_path: impl AsRef<Path>,
+        key_path: impl AsRef<Path>,
+        ca_cert_paths: &[impl AsRef<Path>],
+        timeouts: ClientTimeouts,
+        name_verification: PeerNameVerification,
+        dscp_classes: &[DscpClass],
+    ) -> Result<Self> {
+        let stats = ConnectionStats::default();
+        let tls_config = |alpn: &[&[u8]]| -> Result<ClientConfig> {
+            let mut config = mtls_rustls_config(&cert_path, &key_path, ca_cert_paths, name_verification)?;
+            config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+            config.key_log = Arc::new(HandshakeCounter(stats.handshakes.clone()));
+            Ok(config)
+        };
+        let builder = |config: ClientConfig| {
+            Client::builder()
+                .use_preconfigured_tls(config)
+                .connect_timeout(timeouts.connect)
+                .timeout(timeouts.request)
+        };
+
+        let mut marked = HashMap::new();
+        for class in dscp_classes {
+            if marked.contains_key(&class.dscp) {
+                continue;
+            }
+            let client = |http1_only| -> Result<MarkedClient> {
+                Ok(MarkedClient::new(tls_config(&[])?, class.dscp, timeouts.connect, timeouts.request, http1_only))
+            };
+            marked.insert(class.dscp, (client(false)?, client(true)?));
+        }
+
+        Ok(Self {
+            h2: builder(tls_config(&[b"h2", b"http/1.1"])?)
+                .build()
+                .context("Failed to build HTTPS client")?,
+            http1: builder(tls_config(&[b"http/1.1"])?)
+                .http1_only()
+                .build()
+                .context("Failed to build HTTP/1.1 client")?,
+            dscp_classes: dscp_classes.to_vec(),
+            marked,
+            stats,
+        })
+    }
+
+    /// Use one client for every protocol (no per-peer preference)
+    ///
+    /// Handshakes made by `client` aren't counted in `stats()`.
+    pub fn from_client(client: Client) -> Self {
+        Self {
+            h2: client.clone(),
+            http1: client,
+            dscp_classes: Vec::new(),
+            marked: HashMap::new(),
+            stats: ConnectionStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &ConnectionStats {
+        &self.stats
+    }
+
+    /// Client to use for a peer with the given protocol preference
+    pub fn for_protocol(&self, protocol: PeerProtocol) -> &Client {
+        match protocol {
+            PeerProtocol::H2 => &self.h2,
+            PeerProtocol::Http1 => &self.http1,
+        }
+    }
+
+    /// Marked client for a message of `priority`, if its priority falls in a DSCP class
+    pub fn marked_for(&self, priority: u8, protocol: PeerProtocol) -> Option<&MarkedClient> {
+        let (h2, http1) = self.marked.get(&dscp::dscp_for(&self.dscp_classes, priority)?)?;
+        match protocol {
+            PeerProtocol::H2 => Some(h2),
+            PeerProtocol::Http1 => Some(http1),
+        }
+    }
+}
+
+/// rustls client configuration with our identity and the mesh CA(s) trusted
+fn mtls_rustls_config(
+    cert_path: impl AsRef<Path>,
+    key_path: impl AsRef<Path>,
+    ca_cert_paths: &[impl AsRef<Path>],
+    name_verification: PeerNameVerification,
+) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    for ca_cert in load_ca_bundle(ca_cert_paths)? {
+        roots.add(&ca_cert).context("Failed to add CA certificate to trust store")?;
+    }
+
+    let verifier: Arc<dyn ServerCertVerifier> = match name_verification {
+        // rustls' own verifier, which checks the SANs against the host dialled
+        PeerNameVerification::Strict => Arc::new(WebPkiVerifier::new(roots, None)),
+        PeerNameVerification::Permissive => Arc::new(ChainOnlyVerifier { roots }),
+    };
 
-    Ok(client)
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(verifier)
+        .with_client_auth_cert(load_cert(cert_path)?, load_private_key(key_path)?)
+        .context("Failed to use certificate and key as client identity")
+}
+
+/// Accepts a server certificate that chains to a trusted CA, without
+/// checking that it names the host dialled
+struct ChainOnlyVerifier {
+    roots: RootCertStore,
+}
+
+impl ServerCertVerifier for ChainOnlyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let cert = ParsedCertificate::try_from(end_entity)?;
+        verify_server_cert_signed_by_trust_anchor(&cert, &self.roots, intermediates, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
 }
 
 /// Makes a GET request to the specified URL
@@ -94,8 +295,85 @@ mod tests {
         let client = create_mtls_client(
             "certs/gateway-a.crt",
             "certs/gateway-a.key",
-            "certs/ca.crt",
+            &["certs/ca.crt"],
+            ClientTimeouts::default(),
+            PeerNameVerification::Strict,
         );
         assert!(client.is_ok(), "Failed to create mTLS client");
     }
+
+    #[tokio::test]
+    async fn test_unresponsive_peer_times_out() {
+        // Accepts connections but never answers, not even the TLS handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let timeouts = ClientTimeouts {
+            connect: Duration::from_millis(200),
+            request: Duration::from_millis(300),
+        };
+        let client = create_mtls_client("certs/gateway-a.crt", "certs/gateway-a.key", &["certs/ca.crt"], timeouts, PeerNameVerification::Strict).unwrap();
+
+        let started = std::time::Instant::now();
+        let err = client.get(format!("https://{}/health", address)).send().await.unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_peer_cert_must_name_dialled_address_when_strict() {
+        use crate::test_util::TestPki;
+        use axum::{routing, Router};
+
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let served = pki.serve("gateway-b", Router::new().route("/health", routing::get(|| async { "OK" }))).await;
+
+        // Reach the same server via 127.0.0.2, which its certificate doesn't name
+        let listener = tokio::net::TcpListener::bind("127.0.0.2:0").await.unwrap();
+        let unnamed = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut outbound = tokio::net::TcpStream::connect(served).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                });
+            }
+        });
+
+        let client = |verification| {
+            create_mtls_client(
+                pki.cert_path("gateway-a"),
+                pki.key_path("gateway-a"),
+                &[pki.ca_path()],
+                ClientTimeouts::default(),
+                verification,
+            )
+            .unwrap()
+        };
+        let strict = client(PeerNameVerification::Strict);
+        let permissive = client(PeerNameVerification::Permissive);
+
+        assert!(get(&strict, &format!("https://{}/health", served)).await.is_ok());
+        let err = get(&strict, &format!("https://{}/health", unnamed)).await.unwrap_err();
+        assert!(format!("{:?}", err).contains("NotValidForName"), "{:?}", err);
+        assert_eq!(get(&permissive, &format!("https://{}/health", unnamed)).await.unwrap(), "OK");
+
+        // Permissive still requires a certificate from a trusted CA
+        let other_ca = TestPki::generate(&["gateway-a"]);
+        let untrusting = create_mtls_client(
+            pki.cert_path("gateway-a"),
+            pki.key_path("gateway-a"),
+            &[other_ca.ca_path()],
+            ClientTimeouts::default(),
+            PeerNameVerification::Permissive,
+        )
+        .unwrap();
+        assert!(get(&untrusting, &format!("https://{}/health", unnamed)).await.is_err());
+    }
 }
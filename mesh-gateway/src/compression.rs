@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// zstd level used for message content: fast, and most of the gain for text
+const ZSTD_LEVEL: i32 = 3;
+
+/// Codec message content is compressed with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+/// Compress `data` with `codec`
+pub fn compress(codec: Codec, data: &[u8]) -> Result<Vec<u8>> {
+    match codec {
+        Codec::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Codec::Zstd => Ok(zstd::encode_all(data, ZSTD_LEVEL)?),
+    }
+}
+
+/// Decompress `data` produced by `compress`, failing rather than
+/// expanding it past `max_len` bytes
+pub fn decompress(codec: Codec, data: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    let decoder: Box<dyn Read + '_> = match codec {
+        Codec::Gzip => Box::new(GzDecoder::new(data)),
+        Codec::Zstd => Box::new(zstd::Decoder::new(data)?),
+    };
+
+    let mut output = Vec::new();
+    decoder
+        .take(max_len as u64 + 1)
+        .read_to_end(&mut output)
+        .with_context(|| format!("Content is not valid {:?} data", codec))?;
+    if output.len() > max_len {
+        anyhow::bail!("Decompressed content exceeds {} bytes", max_len);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompression_is_bounded() {
+        let zeros = vec![0u8; 100_000];
+        for codec in [Codec::Gzip, Codec::Zstd] {
+            let compressed = compress(codec, &zeros).unwrap();
+            assert!(compressed.len() < 1_000);
+
+            assert_eq!(decompress(codec, &compressed, zeros.len()).unwrap(), zeros);
+            assert!(decompress(codec, &compressed, zeros.len() - 1).is_err());
+            assert!(decompress(codec, b"not compressed", zeros.len()).is_err());
+        }
+    }
+}
@@ -1,7 +1,18 @@
+use crate::circuit_breaker::BreakerPolicy;
+use crate::client::ClientTimeouts;
+use crate::compression::Codec;
+use crate::dscp::DscpClass;
+use crate::forwarding::{RetryPolicy, DEFAULT_QUEUE_DEPTH};
+use crate::routing::BalanceStrategy;
+use crate::types::DEFAULT_MESSAGE_TTL;
+use crate::wire::WireEncoding;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 
 /// Configuration for a gateway node
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +23,20 @@ pub struct GatewayConfig {
     /// Port to listen on
     pub listen_port: u16,
 
+    /// IP address to bind to, e.g. "0.0.0.0" or "::" (default: 127.0.0.1)
+    #[serde(default)]
+    pub bind_address: Option<String>,
+
+    /// Further addresses to serve on besides bind_address:listen_port, as
+    /// "ip:port" (IPv6 in brackets), e.g. to bridge two network segments
+    #[serde(default)]
+    pub additional_listen_addrs: Vec<String>,
+
+    /// Unix domain socket serving the read-only control endpoints
+    /// (`/health`, `/topology`, `/stats`) without TLS (unset: none)
+    #[serde(default)]
+    pub control_socket: Option<String>,
+
     /// Path to certificate file
     #[serde(default = "default_cert_path")]
     pub cert_path: String,
@@ -24,9 +49,343 @@ pub struct GatewayConfig {
     #[serde(default = "default_ca_cert_path")]
     pub ca_cert_path: String,
 
+    /// Further CA certificates trusted alongside `ca_cert_path`,
+    /// e.g. the new CA while certificates are being rotated to it
+    #[serde(default)]
+    pub ca_cert_paths: Vec<String>,
+
+    /// Directory that relative certificate paths are resolved against,
+    /// itself relative to the config file's directory (default: that directory)
+    #[serde(default)]
+    pub base_dir: Option<String>,
+
     /// List of peer gateways
     #[serde(default)]
     pub peers: Vec<PeerConfig>,
+
+    /// Config files merged in as a base before this file's own settings,
+    /// which win; their `[[peers]]` are added to `peers` (relative paths
+    /// are resolved against this config file's directory)
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Directory whose `*.toml` files are all merged in like `include`
+    #[serde(default)]
+    pub peers_dir: Option<String>,
+
+    /// Addresses ("host:port") of gateways to bootstrap from: each one, and
+    /// the connected peers it lists at `/peers`, is added as a peer
+    #[serde(default)]
+    pub seed_peers: Vec<String>,
+
+    /// Seconds between re-querying `seed_peers` for new peers (default: 300)
+    #[serde(default = "default_seed_discovery_secs")]
+    pub seed_discovery_secs: u64,
+
+    /// Serve `POST /admin/peers` and `DELETE /admin/peers/:id` for adding
+    /// and removing peers at runtime
+    #[serde(default)]
+    pub admin_api: bool,
+
+    /// Node ids (client certificate CNs) allowed to use the admin endpoints
+    #[serde(default)]
+    pub admin_identities: Vec<String>,
+
+    /// Lowest TLS version the server accepts: "1.2" or "1.3" (default: 1.2)
+    #[serde(default)]
+    pub min_tls_version: Option<String>,
+
+    /// Cipher suites the server offers, by rustls name (default: rustls' safe defaults)
+    #[serde(default)]
+    pub cipher_suites: Vec<String>,
+
+    /// Whether a peer's certificate must name the host in its configured
+    /// address, or only chain to a trusted CA (default: strict)
+    #[serde(default)]
+    pub peer_name_verification: PeerNameVerification,
+
+    /// Seconds between HTTP/2 keep-alive pings the server sends on idle
+    /// connections (default: no pings)
+    #[serde(default)]
+    pub http2_keep_alive_interval_secs: Option<u64>,
+
+    /// Concurrent HTTP/2 streams the server allows per connection (default: hyper's)
+    #[serde(default)]
+    pub http2_max_concurrent_streams: Option<u32>,
+
+    /// Initial HTTP/2 stream flow-control window, in bytes (default: hyper's)
+    #[serde(default)]
+    pub http2_initial_window_size: Option<u32>,
+
+    /// Serialize forwarded messages per next hop so they arrive in submission order
+    /// Trades throughput for ordering, so it is off by default
+    #[serde(default)]
+    pub ordered_forwarding: bool,
+
+    /// Messages each next hop's send queue holds in ordered forwarding;
+    /// forwards beyond that fail with status `queue_full`
+    #[serde(default = "default_ordered_queue_depth")]
+    pub ordered_queue_depth: usize,
+
+    /// Accept messages into a priority queue and forward them from a
+    /// background worker, answering `accepted` instead of waiting for the
+    /// next hop
+    #[serde(default)]
+    pub queued_forwarding: bool,
+
+    /// At startup, open a connection to each configured peer (by checking its
+    /// health) so the first forwarded message doesn't pay for the handshake
+    #[serde(default)]
+    pub warm_connections: bool,
+
+    /// Report every unroutable destination as HTTP 200 `no_route` and failed
+    /// forwards as HTTP 200 (pre-404/502/503/504 behavior)
+    #[serde(default)]
+    pub legacy_no_route_status: bool,
+
+    /// Remove a peer after it has been continuously down for this many seconds
+    /// (disabled when unset)
+    #[serde(default)]
+    pub peer_max_down_secs: Option<u64>,
+
+    /// Mark a Connected peer Unknown once nothing has been heard from it
+    /// (health check, LSA or message) for this many seconds, without waiting
+    /// for the next health check (disabled when unset)
+    #[serde(default)]
+    pub peer_stale_secs: Option<u64>,
+
+    /// Log which known nodes are reachable and which aren't (at debug level)
+    /// every this many seconds (disabled when unset)
+    #[serde(default)]
+    pub reachability_log_secs: Option<u64>,
+
+    /// Check the certificate, key and CA files every this many seconds and
+    /// reload the server's TLS configuration when they change (disabled when unset)
+    #[serde(default)]
+    pub cert_watch_secs: Option<u64>,
+
+    /// Shell command run whenever a direct peer's status changes, with the
+    /// peer and both statuses in its environment (disabled when unset)
+    #[serde(default)]
+    pub peer_status_command: Option<String>,
+
+    /// Also prune peers listed in this file, not just ones added at runtime
+    #[serde(default)]
+    pub prune_static_peers: bool,
+
+    /// Sign our own LSAs with this node's certificate key
+    #[serde(default)]
+    pub sign_lsas: bool,
+
+    /// Reject LSAs that carry no signature (signed LSAs are always verified)
+    #[serde(default)]
+    pub require_signed_lsas: bool,
+
+    /// Encrypt the content of messages sent from here to the destination's
+    /// public key, so relaying nodes only see ciphertext
+    #[serde(default)]
+    pub encrypt_messages: bool,
+
+    /// Compress the content of messages sent from here when it is longer
+    /// than this many bytes (None = never)
+    #[serde(default)]
+    pub compress_above_bytes: Option<usize>,
+
+    /// Codec for `compress_above_bytes`
+    #[serde(default)]
+    pub compression_codec: Codec,
+
+    /// Body encoding for messages, acks and LSAs sent to other gateways;
+    /// peers accept either, so it can differ between nodes
+    #[serde(default)]
+    pub wire_encoding: WireEncoding,
+
+    /// DSCP marks for forwarded messages by priority; each message takes the
+    /// class with the highest `min_priority` not above its own (none: unmarked)
+    #[serde(default)]
+    pub dscp_classes: Vec<DscpClass>,
+
+    /// Certificates of nodes messages can be encrypted to, besides the ones
+    /// learned from signed LSAs
+    #[serde(default)]
+    pub recipient_certs: Vec<String>,
+
+    /// URL that undeliverable messages are POSTed to, with the drop reason
+    #[serde(default)]
+    pub dead_letter_url: Option<String>,
+
+    /// JSONL file recording every message this node originates, forwards or
+    /// delivers (unset: no audit log)
+    #[serde(default)]
+    pub audit_log_path: Option<String>,
+
+    /// Size in bytes at which the audit log is rotated to `<path>.1`
+    #[serde(default = "default_audit_log_max_bytes")]
+    pub audit_log_max_bytes: u64,
+
+    /// Number of hops our LSAs are flooded before they stop propagating
+    /// (1 = direct neighbors only; unset floods to the whole mesh)
+    #[serde(default)]
+    pub lsa_flood_scope: Option<u8>,
+
+    /// Longest route, in hops, used to reach a destination; one only
+    /// reachable over more hops is reported as `no_route` (unset = no limit)
+    #[serde(default)]
+    pub max_route_hops: Option<u8>,
+
+    /// Among routes with the same hop count, prefer ones through nodes whose
+    /// LSAs advertise a long-stable topology (`stability_hint`)
+    #[serde(default)]
+    pub stability_tiebreak: bool,
+
+    /// How to choose between equal-cost routes to a destination
+    #[serde(default)]
+    pub route_selection: RouteSelection,
+
+    /// When a message this node originates can't reach its next hop, resend
+    /// it along a backup route that avoids the primary route's relays
+    #[serde(default)]
+    pub backup_route_failover: bool,
+
+    /// What `/health/ready` requires before reporting the node ready
+    #[serde(default)]
+    pub readiness: ReadinessCriteria,
+
+    /// Maximum number of message receipts kept (least recently used are evicted)
+    #[serde(default = "default_receipt_capacity")]
+    pub receipt_capacity: usize,
+
+    /// Seconds a message receipt is kept before it expires
+    #[serde(default = "default_receipt_ttl_secs")]
+    pub receipt_ttl_secs: u64,
+
+    /// Hop limit for messages sent from this node that don't specify one
+    #[serde(default = "default_message_ttl")]
+    pub message_ttl: u8,
+
+    /// Wait up to this many milliseconds for the destination's delivery ack
+    /// before answering /message/send (unset: answer as soon as the
+    /// forwarding chain returns, without waiting for an ack)
+    #[serde(default)]
+    pub ack_timeout_ms: Option<u64>,
+
+    /// Milliseconds allowed to connect to a peer (TCP and TLS handshake)
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+
+    /// Milliseconds allowed for a whole request to a peer, including its response
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+
+    /// Times to retry a forward that failed to reach the next hop
+    #[serde(default = "default_forward_retries")]
+    pub forward_retries: u32,
+
+    /// Delay before the first retry in milliseconds; doubled for each further retry
+    #[serde(default = "default_forward_retry_base_ms")]
+    pub forward_retry_base_ms: u64,
+
+    /// Most requests to peers (forwarded messages, acks and LSAs) in
+    /// flight at once; more wait for one to finish (unset: unlimited)
+    #[serde(default)]
+    pub max_outbound_requests: Option<usize>,
+
+    /// Consecutive failed forwards to a next hop (after retries) that open
+    /// its circuit, so forwards to it fail fast as `circuit_open` (None = never)
+    #[serde(default)]
+    pub circuit_breaker_failures: Option<u32>,
+
+    /// Seconds within which those failures must happen
+    #[serde(default = "default_circuit_breaker_window_secs")]
+    pub circuit_breaker_window_secs: u64,
+
+    /// Seconds an open circuit fails fast before one forward is let through
+    /// as a probe
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64,
+
+    /// Number of recent message ids remembered for duplicate detection
+    #[serde(default = "default_dedup_capacity")]
+    pub dedup_capacity: usize,
+
+    /// Seconds a message id is remembered; a repeat within this window is a duplicate
+    #[serde(default = "default_dedup_ttl_secs")]
+    pub dedup_ttl_secs: u64,
+
+    /// Largest message `content` accepted, in bytes; longer messages are
+    /// rejected with `payload_too_large` instead of being forwarded
+    #[serde(default = "default_max_message_bytes")]
+    pub max_message_bytes: usize,
+
+    /// Seconds a forwarded message's timestamp may differ from our clock;
+    /// older (or further future) messages are rejected as `replay_rejected`
+    #[serde(default = "default_max_clock_skew_secs")]
+    pub max_clock_skew_secs: u64,
+
+    /// Number of recent message nonces remembered to reject replays within
+    /// the clock-skew window
+    #[serde(default = "default_nonce_cache_capacity")]
+    pub nonce_cache_capacity: usize,
+
+    /// Requests per second each peer may make to `/message/receive`
+    /// (unset: unlimited); beyond it they are refused with 429
+    #[serde(default)]
+    pub message_rate_limit: Option<f64>,
+
+    /// Requests a peer may make to `/message/receive` in a burst
+    #[serde(default = "default_message_rate_burst")]
+    pub message_rate_burst: u32,
+
+    /// LSAs per second each peer may POST to `/topology/lsa` (unset: unlimited)
+    #[serde(default)]
+    pub lsa_rate_limit: Option<f64>,
+
+    /// LSAs a peer may POST to `/topology/lsa` in a burst
+    #[serde(default = "default_lsa_rate_burst")]
+    pub lsa_rate_burst: u32,
+
+    /// Minimum milliseconds between re-floods of one originator's LSAs;
+    /// LSAs arriving faster are coalesced to the latest (0 = no limit)
+    #[serde(default)]
+    pub lsa_min_flood_interval_ms: u64,
+
+    /// Seconds between periodic broadcasts of our own LSA
+    #[serde(default = "default_lsa_interval_secs")]
+    pub lsa_interval_secs: u64,
+
+    /// Seconds between peer health check rounds
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+
+    /// Seconds a peer has to answer a health check before it counts as down
+    #[serde(default = "default_health_check_timeout_secs")]
+    pub health_check_timeout_secs: u64,
+
+    /// Drop LSAs whose timestamp is older than this many seconds, so crashed
+    /// nodes fall out of the topology
+    #[serde(default = "default_lsa_max_age_secs")]
+    pub lsa_max_age_secs: u64,
+
+    /// Treat configuration warnings (e.g. a peer address that is our own
+    /// listen address) as errors
+    #[serde(default)]
+    pub strict_validation: bool,
+
+    /// Seconds to let in-flight requests finish after a shutdown signal
+    #[serde(default = "default_shutdown_drain_secs")]
+    pub shutdown_drain_secs: u64,
+
+    /// On shutdown, advertise an empty neighbor list so peers stop routing through us
+    #[serde(default = "default_withdraw_on_shutdown")]
+    pub withdraw_on_shutdown: bool,
+
+    /// Seconds without topology changes before a burst counts as converged
+    #[serde(default = "default_convergence_quiet_secs")]
+    pub convergence_quiet_secs: u64,
+
+    /// Log output format (overridden by the MESH_LOG_FORMAT environment variable)
+    #[serde(default)]
+    pub log_format: LogFormat,
 }
 
 /// Configuration for a peer gateway
@@ -37,8 +396,104 @@ pub struct PeerConfig {
 
     /// Address of the peer (host:port)
     pub address: String,
+
+    /// HTTP protocol to use when forwarding messages to this peer
+    #[serde(default)]
+    pub protocol: PeerProtocol,
+
+    /// Routing cost of the link to this peer (higher is less preferred)
+    #[serde(default = "default_link_cost")]
+    pub cost: u32,
+}
+
+/// HTTP protocol preference for a peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerProtocol {
+    /// Negotiate HTTP/2 via ALPN, falling back to HTTP/1.1
+    #[default]
+    H2,
+    /// Always use HTTP/1.1 (e.g. behind a load balancer that can't handle h2)
+    Http1,
 }
 
+/// How a peer's certificate is checked against the address dialled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerNameVerification {
+    /// The certificate's SANs must include the address' host: its DNS name,
+    /// or its IP address as an IP SAN
+    #[default]
+    Strict,
+    /// Any certificate issued by a trusted CA is accepted, whatever it names
+    /// (e.g. peers reached through NAT or by an address not in their cert)
+    Permissive,
+}
+
+/// Route selection policy when several routes share the lowest cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSelection {
+    /// Always use the same route (ties broken by node_id)
+    #[default]
+    Single,
+    /// Spread messages across all equal-cost routes by hashing their message_id
+    Ecmp,
+    /// Alternate between the equal-cost next hops
+    RoundRobin,
+    /// Pick one of the equal-cost next hops at random
+    Random,
+    /// Pick the equal-cost next hop that has gone unused the longest
+    LeastRecentlyUsed,
+}
+
+impl RouteSelection {
+    /// The load balancing strategy this policy uses, if any
+    pub fn balance_strategy(self) -> Option<BalanceStrategy> {
+        match self {
+            RouteSelection::Single | RouteSelection::Ecmp => None,
+            RouteSelection::RoundRobin => Some(BalanceStrategy::RoundRobin),
+            RouteSelection::Random => Some(BalanceStrategy::Random),
+            RouteSelection::LeastRecentlyUsed => Some(BalanceStrategy::LeastRecentlyUsed),
+        }
+    }
+}
+
+/// When a node counts as ready to take traffic (`/health/ready`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessCriteria {
+    /// At least one connected peer, or an LSA learned from another node
+    #[default]
+    PeerOrRouting,
+    /// At least one connected peer
+    ConnectedPeer,
+    /// An LSA learned from another node, so routes beyond direct peers exist
+    RoutingInitialized,
+}
+
+/// Log output format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+/// A file of peer definitions pulled in via `peers_dir`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeerFile {
+    #[serde(default)]
+    peers: Vec<PeerConfig>,
+}
+
+/// Address we bind to when `bind_address` isn't set
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+
 fn default_cert_path() -> String {
     "certs/gateway.crt".to_string()
 }
@@ -51,41 +506,687 @@ fn default_ca_cert_path() -> String {
     "certs/ca.crt".to_string()
 }
 
+fn default_link_cost() -> u32 {
+    1
+}
+
+fn default_ordered_queue_depth() -> usize {
+    DEFAULT_QUEUE_DEPTH
+}
+
+fn default_receipt_capacity() -> usize {
+    10_000
+}
+
+fn default_receipt_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_message_ttl() -> u8 {
+    DEFAULT_MESSAGE_TTL
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_request_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_forward_retries() -> u32 {
+    3
+}
+
+fn default_forward_retry_base_ms() -> u64 {
+    100
+}
+
+fn default_circuit_breaker_window_secs() -> u64 {
+    60
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_dedup_capacity() -> usize {
+    10_000
+}
+
+fn default_dedup_ttl_secs() -> u64 {
+    300
+}
+
+fn default_max_message_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_max_clock_skew_secs() -> u64 {
+    30
+}
+
+fn default_nonce_cache_capacity() -> usize {
+    10_000
+}
+
+fn default_audit_log_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_message_rate_burst() -> u32 {
+    100
+}
+
+fn default_lsa_rate_burst() -> u32 {
+    20
+}
+
+fn default_lsa_interval_secs() -> u64 {
+    30
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    15
+}
+
+fn default_health_check_timeout_secs() -> u64 {
+    5
+}
+
+fn default_lsa_max_age_secs() -> u64 {
+    90
+}
+
+fn default_shutdown_drain_secs() -> u64 {
+    10
+}
+
+fn default_withdraw_on_shutdown() -> bool {
+    true
+}
+
+fn default_convergence_quiet_secs() -> u64 {
+    3
+}
+
+fn default_seed_discovery_secs() -> u64 {
+    300
+}
+
+/// Peers defined in an included config file, kept out of the merged settings
+/// so a duplicate can be reported with the file it came from
+pub type PeerSource = (PathBuf, Vec<PeerConfig>);
+
+/// Read a config file as TOML with the files it `include`s merged in,
+/// along with the peers those files define
+pub fn load_config_table(path: &Path) -> Result<(toml::Table, Vec<PeerSource>)> {
+    let mut chain = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+    let mut included_peers = Vec::new();
+    let table = read_config_table(path, &mut chain, &mut included_peers)?;
+    Ok((table, included_peers))
+}
+
+/// Read one config file and, recursively, the files it includes
+///
+/// Included files are applied in order as a base (later files win), then the
+/// file's own settings over them. `chain` holds the files being read, from
+/// the main config down, to catch include cycles.
+fn read_config_table(path: &Path, chain: &mut Vec<PathBuf>, included_peers: &mut Vec<PeerSource>) -> Result<toml::Table> {
+    let contents = fs::read_to_string(path)
+        .context(format!("Failed to read config file: {:?}", path))?;
+    let table: toml::Table = toml::from_str(&contents)
+        .context(format!("Failed to parse TOML configuration: {:?}", path))?;
+
+    let includes: Vec<String> = match table.get("include") {
+        Some(include) => include.clone().try_into().context(format!("Invalid include list in {:?}", path))?,
+        None => Vec::new(),
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut merged = toml::Table::new();
+    for include in includes {
+        let file = dir.join(include);
+        let canonical = file
+            .canonicalize()
+            .context(format!("Failed to read included config file: {:?}", file))?;
+        if let Some(start) = chain.iter().position(|seen| *seen == canonical) {
+            let cycle: Vec<String> = chain[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|file| file.display().to_string())
+                .collect();
+            anyhow::bail!("Config include cycle: {}", cycle.join(" -> "));
+        }
+
+        chain.push(canonical);
+        let mut base = read_config_table(&file, chain, included_peers)?;
+        chain.pop();
+
+        base.remove("include");
+        if let Some(peers) = base.remove("peers") {
+            let peers = peers
+                .try_into()
+                .context(format!("Failed to parse peers in included config file: {:?}", file))?;
+            included_peers.push((file, peers));
+        }
+        merged.extend(base);
+    }
+
+    merged.extend(table);
+    Ok(merged)
+}
+
+/// Whether `address` has the "host:port" form peers are dialed with
+/// `path` joined onto `base` unless it is absolute, with `dir/..` pairs
+/// collapsed so the result reads like the path an operator would write
+fn resolve_path(base: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(resolved.components().next_back(), Some(Component::Normal(_))) => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+pub(crate) fn is_host_port(address: &str) -> bool {
+    let Some((host, port)) = address.rsplit_once(':') else {
+        return false;
+    };
+    if !port.parse::<u16>().is_ok_and(|port| port != 0) {
+        return false;
+    }
+
+    if let Some(ipv6) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return ipv6.parse::<std::net::Ipv6Addr>().is_ok();
+    }
+    !host.is_empty() && !host.contains(|c: char| c == ':' || c == '/' || c.is_whitespace())
+}
+
+/// Whether `address` ("host:port") points at a loopback host on `port`
+fn is_loopback_address(address: &str, port: u16) -> bool {
+    let Some((host, peer_port)) = address.rsplit_once(':') else {
+        return false;
+    };
+    if peer_port.parse::<u16>().ok() != Some(port) {
+        return false;
+    }
+
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Machine-readable summary of a gateway's startup, for log aggregation
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupDiagnostics {
+    pub event: &'static str,
+    pub node_id: String,
+    pub version: &'static str,
+    pub listen_addr: String,
+    pub cert_path: String,
+    pub key_path: String,
+    pub ca_cert_path: String,
+    pub peer_count: usize,
+    /// Fully resolved configuration (after defaults and path overrides)
+    pub config: GatewayConfig,
+}
+
+impl StartupDiagnostics {
+    pub fn from_config(config: &GatewayConfig) -> Self {
+        Self {
+            event: "startup",
+            node_id: config.node_id.clone(),
+            version: env!("CARGO_PKG_VERSION"),
+            listen_addr: config.listen_addr(),
+            cert_path: config.cert_path.clone(),
+            key_path: config.key_path.clone(),
+            ca_cert_path: config.ca_cert_path.clone(),
+            peer_count: config.peers.len(),
+            config: config.clone(),
+        }
+    }
+
+    /// Emit as a single structured tracing event
+    pub fn emit(&self) {
+        tracing::info!(
+            target: "startup",
+            node_id = %self.node_id,
+            version = self.version,
+            listen_addr = %self.listen_addr,
+            cert_path = %self.cert_path,
+            key_path = %self.key_path,
+            ca_cert_path = %self.ca_cert_path,
+            peer_count = self.peer_count,
+            config = %serde_json::to_string(&self.config).unwrap_or_default(),
+            "startup"
+        );
+    }
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            node_id: String::new(),
+            listen_port: 0,
+            bind_address: None,
+            additional_listen_addrs: Vec::new(),
+            control_socket: None,
+            cert_path: default_cert_path(),
+            key_path: default_key_path(),
+            ca_cert_path: default_ca_cert_path(),
+            ca_cert_paths: Vec::new(),
+            base_dir: None,
+            peers: Vec::new(),
+            include: Vec::new(),
+            peers_dir: None,
+            seed_peers: Vec::new(),
+            seed_discovery_secs: default_seed_discovery_secs(),
+            admin_api: false,
+            admin_identities: Vec::new(),
+            min_tls_version: None,
+            cipher_suites: Vec::new(),
+            peer_name_verification: PeerNameVerification::default(),
+            http2_keep_alive_interval_secs: None,
+            http2_max_concurrent_streams: None,
+            http2_initial_window_size: None,
+            ordered_forwarding: false,
+            ordered_queue_depth: default_ordered_queue_depth(),
+            queued_forwarding: false,
+            warm_connections: false,
+            legacy_no_route_status: false,
+            peer_max_down_secs: None,
+            peer_stale_secs: None,
+            reachability_log_secs: None,
+            cert_watch_secs: None,
+            peer_status_command: None,
+            prune_static_peers: false,
+            sign_lsas: false,
+            require_signed_lsas: false,
+            encrypt_messages: false,
+            compress_above_bytes: None,
+            compression_codec: Codec::default(),
+            wire_encoding: WireEncoding::default(),
+            dscp_classes: Vec::new(),
+            recipient_certs: Vec::new(),
+            dead_letter_url: None,
+            audit_log_path: None,
+            audit_log_max_bytes: default_audit_log_max_bytes(),
+            lsa_flood_scope: None,
+            max_route_hops: None,
+            stability_tiebreak: false,
+            route_selection: RouteSelection::default(),
+            backup_route_failover: false,
+            readiness: ReadinessCriteria::default(),
+            receipt_capacity: default_receipt_capacity(),
+            receipt_ttl_secs: default_receipt_ttl_secs(),
+            message_ttl: default_message_ttl(),
+            ack_timeout_ms: None,
+            connect_timeout_ms: default_connect_timeout_ms(),
+            request_timeout_ms: default_request_timeout_ms(),
+            forward_retries: default_forward_retries(),
+            forward_retry_base_ms: default_forward_retry_base_ms(),
+            max_outbound_requests: None,
+            circuit_breaker_failures: None,
+            circuit_breaker_window_secs: default_circuit_breaker_window_secs(),
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown_secs(),
+            dedup_capacity: default_dedup_capacity(),
+            dedup_ttl_secs: default_dedup_ttl_secs(),
+            max_message_bytes: default_max_message_bytes(),
+            max_clock_skew_secs: default_max_clock_skew_secs(),
+            nonce_cache_capacity: default_nonce_cache_capacity(),
+            message_rate_limit: None,
+            message_rate_burst: default_message_rate_burst(),
+            lsa_rate_limit: None,
+            lsa_rate_burst: default_lsa_rate_burst(),
+            lsa_min_flood_interval_ms: 0,
+            lsa_interval_secs: default_lsa_interval_secs(),
+            health_check_interval_secs: default_health_check_interval_secs(),
+            health_check_timeout_secs: default_health_check_timeout_secs(),
+            lsa_max_age_secs: default_lsa_max_age_secs(),
+            strict_validation: false,
+            shutdown_drain_secs: default_shutdown_drain_secs(),
+            withdraw_on_shutdown: default_withdraw_on_shutdown(),
+            convergence_quiet_secs: default_convergence_quiet_secs(),
+            log_format: LogFormat::default(),
+        }
+    }
+}
+
 impl GatewayConfig {
     /// Load configuration from a TOML file
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
-        let contents = fs::read_to_string(path.as_ref())
-            .context(format!("Failed to read config file: {:?}", path.as_ref()))?;
-
-        let config: GatewayConfig = toml::from_str(&contents)
+        let (mut table, included_peers) = load_config_table(path.as_ref())?;
+        // Out of range for a u16, which serde would only report as a type
+        // mismatch. Noted here and reported along with whatever `validate`
+        // finds; a placeholder lets the rest of the file parse.
+        let mut load_errors = Vec::new();
+        if let Some(port) = table.get("listen_port").and_then(toml::Value::as_integer)
+            && !(1..=65535).contains(&port)
+        {
+            load_errors.push(format!("Invalid listen_port {}: must be between 1 and 65535", port));
+            table.insert("listen_port".to_string(), toml::Value::Integer(1));
+        }
+        let mut config: GatewayConfig = toml::Value::Table(table)
+            .try_into()
             .context("Failed to parse TOML configuration")?;
 
-        // Validate peer addresses don't contain protocol
-        for peer in &config.peers {
-            if peer.address.contains("://") {
-                anyhow::bail!(
-                    "Invalid peer address '{}' for peer '{}': address must be in 'host:port' format, not a URL. Remove 'http://' or 'https://' prefix.",
-                    peer.address,
-                    peer.node_id
-                );
-            }
+        let config_dir = path.as_ref().parent().unwrap_or(Path::new(""));
+        config.merge_peer_files(path.as_ref(), config_dir, included_peers)?;
+
+        for warning in config.validate_with(load_errors)? {
+            tracing::warn!("{}", warning);
         }
 
         // Override cert paths if they use the gateway's node_id
-        let mut config = config;
         if config.cert_path == default_cert_path() {
             config.cert_path = format!("certs/{}.crt", config.node_id);
         }
         if config.key_path == default_key_path() {
             config.key_path = format!("certs/{}.key", config.node_id);
         }
+        config.resolve_paths(config_dir);
 
         Ok(config)
     }
 
-    /// Get the listen address
+    /// Make relative certificate (and audit log) paths relative to
+    /// `base_dir` instead of the working directory, so the gateway can be
+    /// started from anywhere
+    fn resolve_paths(&mut self, config_dir: &Path) {
+        let base_dir = match &self.base_dir {
+            Some(dir) => resolve_path(config_dir, dir),
+            None => config_dir.to_path_buf(),
+        };
+
+        let paths = [&mut self.cert_path, &mut self.key_path, &mut self.ca_cert_path]
+            .into_iter()
+            .chain(self.ca_cert_paths.iter_mut())
+            .chain(self.recipient_certs.iter_mut())
+            .chain(self.audit_log_path.iter_mut());
+        for path in paths {
+            *path = resolve_path(&base_dir, path).to_string_lossy().into_owned();
+        }
+    }
+
+    /// Check the configuration for mistakes
+    ///
+    /// Every hard error found is reported together in one multi-line `Err`,
+    /// so a broken file can be fixed in one pass. Suspicious but workable
+    /// settings are returned as warnings, or reported as errors too when
+    /// `strict_validation` is set.
+    pub fn validate(&self) -> Result<Vec<String>> {
+        self.validate_with(Vec::new())
+    }
+
+    /// `validate`, also reporting `errors` already found while loading the file
+    fn validate_with(&self, mut errors: Vec<String>) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+
+        if self.node_id.trim().is_empty() {
+            errors.push("node_id must not be empty".to_string());
+        }
+
+        if self.listen_port == 0 {
+            errors.push("Invalid listen_port 0: must be between 1 and 65535".to_string());
+        }
+
+        let listen_addr = self.listen_addr();
+        let bind_ip = match listen_addr.parse::<SocketAddr>() {
+            Ok(addr) => Some(addr.ip()),
+            Err(_) => {
+                errors.push(format!(
+                    "Invalid bind_address '{}': must be an IPv4 or IPv6 address, e.g. '0.0.0.0' or '::'",
+                    self.bind_address.as_deref().unwrap_or(DEFAULT_BIND_ADDRESS)
+                ));
+                None
+            }
+        };
+        if bind_ip.is_some()
+            && let Err(e) = self.listen_addrs()
+        {
+            errors.push(e.to_string());
+        }
+
+        let cert_paths = [("cert_path", &self.cert_path), ("key_path", &self.key_path), ("ca_cert_path", &self.ca_cert_path)]
+            .into_iter()
+            .chain(self.ca_cert_paths.iter().map(|path| ("ca_cert_paths entry", path)));
+        for (name, path) in cert_paths {
+            if path.trim().is_empty() {
+                errors.push(format!("{} must not be empty", name));
+            }
+        }
+
+        let mut node_ids = HashSet::new();
+        let mut addresses: HashMap<&str, &str> = HashMap::new();
+        for peer in &self.peers {
+            if peer.node_id.trim().is_empty() {
+                errors.push(format!("Peer at '{}' has an empty node_id", peer.address));
+            } else if !node_ids.insert(peer.node_id.as_str()) {
+                errors.push(format!("Peer '{}' is listed more than once", peer.node_id));
+            }
+
+            if let Some(other) = addresses.insert(peer.address.as_str(), peer.node_id.as_str()) {
+                errors.push(format!(
+                    "Peers '{}' and '{}' have the same address '{}'",
+                    other, peer.node_id, peer.address
+                ));
+            }
+
+            // Validate peer addresses don't contain protocol
+            if peer.address.contains("://") {
+                errors.push(format!(
+                    "Invalid peer address '{}' for peer '{}': address must be in 'host:port' format, not a URL. Remove 'http://' or 'https://' prefix.",
+                    peer.address,
+                    peer.node_id
+                ));
+            } else if !is_host_port(&peer.address) {
+                errors.push(format!(
+                    "Invalid peer address '{}' for peer '{}': expected 'host:port' (IPv6 hosts in brackets, e.g. '[::1]:8002')",
+                    peer.address,
+                    peer.node_id
+                ));
+            }
+
+            if peer.cost == 0 {
+                errors.push(format!("Invalid cost for peer '{}': link cost must be at least 1", peer.node_id));
+            }
+
+            if peer.node_id == self.node_id {
+                warnings.push(format!(
+                    "Peer '{}' at '{}' is this node itself; it is ignored",
+                    peer.node_id, peer.address
+                ));
+            }
+
+            // When we listen on loopback, a loopback peer on our port is ourselves
+            let listens_on_loopback = bind_ip.is_some_and(|ip| ip.is_loopback() || ip.is_unspecified());
+            if listens_on_loopback && is_loopback_address(&peer.address, self.listen_port) {
+                warnings.push(format!(
+                    "Peer '{}' address '{}' is this node's own listen address (listen_port = {}); it would connect to itself",
+                    peer.node_id, peer.address, self.listen_port
+                ));
+            }
+        }
+
+        if self.admin_api && self.admin_identities.is_empty() {
+            errors.push("admin_api requires at least one node id in admin_identities".to_string());
+        }
+
+        for seed in &self.seed_peers {
+            if !is_host_port(seed) {
+                errors.push(format!(
+                    "Invalid seed peer address '{}': expected 'host:port' (IPv6 hosts in brackets, e.g. '[::1]:8002')",
+                    seed
+                ));
+            }
+        }
+
+        if self.ordered_queue_depth == 0 {
+            errors.push("Invalid ordered_queue_depth: a send queue holds at least 1 message".to_string());
+        }
+
+        if self.max_route_hops == Some(0) {
+            errors.push("Invalid max_route_hops: a route has at least 1 hop".to_string());
+        }
+
+        for (i, class) in self.dscp_classes.iter().enumerate() {
+            if class.dscp > 63 {
+                errors.push(format!("Invalid dscp {} for min_priority {}: DSCP values range from 0 to 63", class.dscp, class.min_priority));
+            }
+            if self.dscp_classes[..i].iter().any(|other| other.min_priority == class.min_priority) {
+                errors.push(format!("dscp_classes has more than one class with min_priority {}", class.min_priority));
+            }
+        }
+
+        if self.circuit_breaker_failures == Some(0) {
+            errors.push("Invalid circuit_breaker_failures: must be at least 1".to_string());
+        }
+
+        if self.strict_validation && !warnings.is_empty() {
+            errors.push(format!("strict_validation: {}", warnings.join("; ")));
+        }
+
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid configuration:\n  - {}", errors.join("\n  - "));
+        }
+
+        Ok(warnings)
+    }
+
+    /// Merge peers from `include` files and `peers_dir` into `peers`
+    ///
+    /// A node_id may only be defined once across the main file and all
+    /// included files; duplicates are reported with both file names.
+    fn merge_peer_files(&mut self, config_path: &Path, base_dir: &Path, included_peers: Vec<PeerSource>) -> Result<()> {
+        let mut files = Vec::new();
+        if let Some(dir) = &self.peers_dir {
+            let dir = base_dir.join(dir);
+            let mut entries: Vec<PathBuf> = fs::read_dir(&dir)
+                .context(format!("Failed to read peers_dir: {:?}", dir))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect();
+            // Directory order is unspecified; keep peer order deterministic
+            entries.sort();
+            files.extend(entries);
+        }
+
+        // Repeats within the main file are left for `validate` to report
+        // along with any other problems
+        let mut sources: HashMap<String, PathBuf> = self
+            .peers
+            .iter()
+            .map(|peer| (peer.node_id.clone(), config_path.to_path_buf()))
+            .collect();
+
+        let mut sourced_peers = included_peers;
+        for file in files {
+            let contents = fs::read_to_string(&file)
+                .context(format!("Failed to read included peer file: {:?}", file))?;
+            let peer_file: PeerFile = toml::from_str(&contents)
+                .context(format!("Failed to parse included peer file: {:?}", file))?;
+            sourced_peers.push((file, peer_file.peers));
+        }
+
+        for (file, peers) in sourced_peers {
+            for peer in peers {
+                if let Some(existing) = sources.get(&peer.node_id) {
+                    anyhow::bail!(
+                        "Peer '{}' is defined in both {:?} and {:?}",
+                        peer.node_id,
+                        existing,
+                        file
+                    );
+                }
+                sources.insert(peer.node_id.clone(), file.clone());
+                self.peers.push(peer);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every CA certificate file to trust: `ca_cert_path`, then `ca_cert_paths`
+    pub fn trusted_ca_paths(&self) -> Vec<String> {
+        std::iter::once(self.ca_cert_path.clone())
+            .chain(self.ca_cert_paths.iter().cloned())
+            .collect()
+    }
+
+    /// Timeouts for the mTLS clients used to reach peers
+    pub fn client_timeouts(&self) -> ClientTimeouts {
+        ClientTimeouts {
+            connect: Duration::from_millis(self.connect_timeout_ms),
+            request: Duration::from_millis(self.request_timeout_ms),
+        }
+    }
+
+    /// Whether unsigned LSAs are rejected: when `require_signed_lsas` is set,
+    /// and whenever this node signs its own (`sign_lsas`)
+    pub fn rejects_unsigned_lsas(&self) -> bool {
+        self.require_signed_lsas || self.sign_lsas
+    }
+
+    /// Retry policy for forwarding messages to the next hop
+    pub fn forward_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.forward_retries,
+            base_delay: Duration::from_millis(self.forward_retry_base_ms),
+        }
+    }
+
+    /// Circuit breaker policy for next hops, if `circuit_breaker_failures` is set
+    pub fn circuit_breaker_policy(&self) -> Option<BreakerPolicy> {
+        self.circuit_breaker_failures.map(|failure_threshold| BreakerPolicy {
+            failure_threshold,
+            window: Duration::from_secs(self.circuit_breaker_window_secs),
+            cooldown: Duration::from_secs(self.circuit_breaker_cooldown_secs),
+        })
+    }
+
+    /// Get the listen address, with IPv6 addresses in brackets
     pub fn listen_addr(&self) -> String {
-        format!("127.0.0.1:{}", self.listen_port)
+        let host = self.bind_address.as_deref().unwrap_or(DEFAULT_BIND_ADDRESS);
+        if host.contains(':') && !host.starts_with('[') {
+            format!("[{}]:{}", host, self.listen_port)
+        } else {
+            format!("{}:{}", host, self.listen_port)
+        }
+    }
+
+    /// Every address to serve on: `listen_addr` first, then
+    /// `additional_listen_addrs`
+    pub fn listen_addrs(&self) -> Result<Vec<SocketAddr>> {
+        let primary = self
+            .listen_addr()
+            .parse()
+            .with_context(|| format!("Invalid listen address '{}'", self.listen_addr()))?;
+
+        let mut addrs = vec![primary];
+        for addr in &self.additional_listen_addrs {
+            let parsed = addr.parse().map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid additional_listen_addrs entry '{}': expected 'ip:port' (IPv6 in brackets, e.g. '[::1]:8001')",
+                    addr
+                )
+            })?;
+            if addrs.contains(&parsed) {
+                anyhow::bail!("Listen address '{}' is listed more than once", addr);
+            }
+            addrs.push(parsed);
+        }
+        Ok(addrs)
     }
 }
 
@@ -106,6 +1207,8 @@ mod tests {
             [[peers]]
             node_id = "gateway-c"
             address = "127.0.0.1:8003"
+            protocol = "http1"
+            cost = 10
         "#;
 
         let config: GatewayConfig = toml::from_str(toml).unwrap();
@@ -114,6 +1217,10 @@ mod tests {
         assert_eq!(config.peers.len(), 2);
         assert_eq!(config.peers[0].node_id, "gateway-b");
         assert_eq!(config.peers[0].address, "127.0.0.1:8002");
+        assert_eq!(config.peers[0].protocol, PeerProtocol::H2);
+        assert_eq!(config.peers[1].protocol, PeerProtocol::Http1);
+        assert_eq!(config.peers[0].cost, 1);
+        assert_eq!(config.peers[1].cost, 10);
     }
 
     #[test]
@@ -128,6 +1235,7 @@ mod tests {
         assert_eq!(config.cert_path, "certs/gateway.crt");
         assert_eq!(config.key_path, "certs/gateway.key");
         assert_eq!(config.ca_cert_path, "certs/ca.crt");
+        assert!(!config.ordered_forwarding);
     }
 
     #[test]
@@ -153,17 +1261,397 @@ mod tests {
         "#;
 
         let config: GatewayConfig = toml::from_str(toml).unwrap();
+        assert!(config.validate().is_err(), "Should detect invalid address with protocol");
+        assert!(config.peers[0].address.contains("://"));
+    }
 
-        // Manually validate (simulating what from_file does)
-        let mut has_error = false;
-        for peer in &config.peers {
-            if peer.address.contains("://") {
-                has_error = true;
-                break;
-            }
+    #[test]
+    fn test_malformed_peer_address_names_peer() {
+        for address in ["127.0.0.1", "gateway-b:", "gateway-b:http", ":8002", "::1:8002", "gateway-b:0", "host/path:8002"] {
+            let config = GatewayConfig {
+                listen_port: 8001,
+                peers: vec![PeerConfig {
+                    node_id: "gateway-b".to_string(),
+                    address: address.to_string(),
+                    protocol: PeerProtocol::default(),
+                    cost: 1,
+                }],
+                ..GatewayConfig::default()
+            };
+            let err = config.validate().unwrap_err().to_string();
+            assert!(err.contains("gateway-b") && err.contains(address), "{}: {}", address, err);
         }
+    }
 
-        assert!(has_error, "Should detect invalid address with protocol");
-        assert!(config.peers[0].address.contains("://"));
+    #[test]
+    fn test_valid_peer_addresses() {
+        for address in ["127.0.0.1:8002", "gateway-b.internal:8443", "[::1]:8002"] {
+            assert!(is_host_port(address), "{}", address);
+        }
+    }
+
+    #[test]
+    fn test_peer_on_own_loopback_port() {
+        let toml = r#"
+            node_id = "gateway-a"
+            listen_port = 8001
+
+            [[peers]]
+            node_id = "gateway-b"
+            address = "localhost:8001"
+
+            [[peers]]
+            node_id = "gateway-c"
+            address = "127.0.0.1:8003"
+
+            [[peers]]
+            node_id = "gateway-d"
+            address = "10.0.0.4:8001"
+        "#;
+
+        let mut config: GatewayConfig = toml::from_str(toml).unwrap();
+        let warnings = config.validate().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("gateway-b"));
+
+        config.strict_validation = true;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_task_intervals_parsed_and_defaulted() {
+        let config: GatewayConfig = toml::from_str("node_id = \"gateway-a\"\nlisten_port = 8001").unwrap();
+        assert_eq!(config.lsa_interval_secs, 30);
+        assert_eq!(config.health_check_interval_secs, 15);
+        assert_eq!(config.health_check_timeout_secs, 5);
+
+        let config: GatewayConfig = toml::from_str(
+            r#"
+            node_id = "gateway-a"
+            listen_port = 8001
+            lsa_interval_secs = 2
+            health_check_interval_secs = 3
+            health_check_timeout_secs = 1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.lsa_interval_secs, 2);
+        assert_eq!(config.health_check_interval_secs, 3);
+        assert_eq!(config.health_check_timeout_secs, 1);
+    }
+
+    #[test]
+    fn test_listen_addr_from_bind_address() {
+        let mut config = GatewayConfig {
+            node_id: "gateway-a".to_string(),
+            listen_port: 8001,
+            ..GatewayConfig::default()
+        };
+        assert_eq!(config.listen_addr(), "127.0.0.1:8001");
+
+        config.bind_address = Some("0.0.0.0".to_string());
+        assert_eq!(config.listen_addr(), "0.0.0.0:8001");
+        assert!(config.validate().is_ok());
+
+        config.bind_address = Some("::1".to_string());
+        assert_eq!(config.listen_addr(), "[::1]:8001");
+        let addr: SocketAddr = config.listen_addr().parse().unwrap();
+        assert!(addr.is_ipv6());
+        assert!(config.validate().is_ok());
+
+        config.bind_address = Some("[::]".to_string());
+        assert_eq!(config.listen_addr(), "[::]:8001");
+    }
+
+    #[test]
+    fn test_invalid_bind_address_rejected() {
+        let config = GatewayConfig {
+            listen_port: 8001,
+            bind_address: Some("gateway.local".to_string()),
+            ..GatewayConfig::default()
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("bind_address"));
+        assert!(err.contains("gateway.local"));
+    }
+
+    fn peer(node_id: &str, address: &str) -> PeerConfig {
+        PeerConfig {
+            node_id: node_id.to_string(),
+            address: address.to_string(),
+            protocol: PeerProtocol::default(),
+            cost: 1,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_peers_rejected() {
+        let config = GatewayConfig {
+            node_id: "gateway-a".to_string(),
+            listen_port: 8001,
+            peers: vec![
+                peer("gateway-b", "10.0.0.2:8002"),
+                peer("gateway-c", "10.0.0.3:8003"),
+                peer("gateway-b", "10.0.0.4:8004"),
+                peer("gateway-d", "10.0.0.3:8003"),
+            ],
+            ..GatewayConfig::default()
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("Peer 'gateway-b' is listed more than once"), "{}", err);
+        assert!(err.contains("Peers 'gateway-c' and 'gateway-d' have the same address '10.0.0.3:8003'"), "{}", err);
+    }
+
+    #[test]
+    fn test_empty_node_id_rejected() {
+        let config = GatewayConfig {
+            node_id: "  ".to_string(),
+            listen_port: 8001,
+            ..GatewayConfig::default()
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("node_id must not be empty"), "{}", err);
+    }
+
+    #[test]
+    fn test_validation_reports_every_problem_at_once() {
+        let config = GatewayConfig {
+            listen_port: 0,
+            cert_path: String::new(),
+            peers: vec![peer("gateway-b", "gateway-b"), peer("gateway-b", "10.0.0.2:8002")],
+            ..GatewayConfig::default()
+        };
+        let err = config.validate().unwrap_err().to_string();
+        let problems: Vec<&str> = err.lines().skip(1).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "  - node_id must not be empty",
+                "  - Invalid listen_port 0: must be between 1 and 65535",
+                "  - cert_path must not be empty",
+                "  - Invalid peer address 'gateway-b' for peer 'gateway-b': expected 'host:port' (IPv6 hosts in brackets, e.g. '[::1]:8002')",
+                "  - Peer 'gateway-b' is listed more than once",
+            ],
+            "{}",
+            err
+        );
+
+        // The same from a file, including a port no u16 can hold
+        let dir = temp_config_dir("problems");
+        fs::write(
+            dir.join("gateway.toml"),
+            "node_id = \"\"\nlisten_port = 70000\n[[peers]]\nnode_id = \"gateway-b\"\naddress = \"10.0.0.2:8002\"\n[[peers]]\nnode_id = \"gateway-b\"\naddress = \"10.0.0.3:8002\"\n",
+        )
+        .unwrap();
+        let err = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap_err().to_string();
+        let problems: Vec<&str> = err.lines().skip(1).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "  - Invalid listen_port 70000: must be between 1 and 65535",
+                "  - node_id must not be empty",
+                "  - Peer 'gateway-b' is listed more than once",
+            ],
+            "{}",
+            err
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_additional_listen_addrs() {
+        let mut config = GatewayConfig {
+            node_id: "gateway-a".to_string(),
+            listen_port: 8001,
+            additional_listen_addrs: vec!["10.0.2.1:8001".to_string(), "[::1]:9001".to_string()],
+            ..GatewayConfig::default()
+        };
+        assert_eq!(
+            config.listen_addrs().unwrap(),
+            vec![
+                "127.0.0.1:8001".parse::<SocketAddr>().unwrap(),
+                "10.0.2.1:8001".parse().unwrap(),
+                "[::1]:9001".parse().unwrap(),
+            ]
+        );
+        assert!(config.validate().is_ok());
+
+        config.additional_listen_addrs = vec!["segment-b:8001".to_string()];
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("additional_listen_addrs"), "{}", err);
+
+        config.additional_listen_addrs = vec!["127.0.0.1:8001".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_startup_diagnostics_json() {
+        let config = GatewayConfig::from_file("configs/gateway-a.toml").unwrap();
+        let diagnostics = StartupDiagnostics::from_config(&config);
+
+        let json: serde_json::Value = serde_json::to_value(&diagnostics).unwrap();
+        assert_eq!(json["event"], "startup");
+        assert_eq!(json["node_id"], "gateway-a");
+        assert_eq!(json["listen_addr"], "127.0.0.1:8001");
+        assert_eq!(json["cert_path"], "certs/gateway-a.crt");
+        assert_eq!(json["peer_count"], 2);
+        assert_eq!(json["config"]["listen_port"], 8001);
+        assert_eq!(json["config"]["peers"][0]["node_id"], "gateway-b");
+    }
+
+    /// Scratch directory for tests that need config files on disk
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mesh-gateway-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("peers.d")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_peers_merged_from_include_and_peers_dir() {
+        let dir = temp_config_dir("merge");
+        fs::write(
+            dir.join("gateway.toml"),
+            r#"
+                node_id = "gateway-a"
+                listen_port = 8001
+                include = ["team-b.toml"]
+                peers_dir = "peers.d"
+
+                [[peers]]
+                node_id = "gateway-b"
+                address = "127.0.0.1:8002"
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.join("team-b.toml"), "[[peers]]\nnode_id = \"gateway-c\"\naddress = \"127.0.0.1:8003\"\n").unwrap();
+        fs::write(dir.join("peers.d/20-e.toml"), "[[peers]]\nnode_id = \"gateway-e\"\naddress = \"127.0.0.1:8005\"\n").unwrap();
+        fs::write(dir.join("peers.d/10-d.toml"), "[[peers]]\nnode_id = \"gateway-d\"\naddress = \"127.0.0.1:8004\"\n").unwrap();
+        fs::write(dir.join("peers.d/README.md"), "not a peer file").unwrap();
+
+        let config = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap();
+        let ids: Vec<&str> = config.peers.iter().map(|p| p.node_id.as_str()).collect();
+        assert_eq!(ids, vec!["gateway-b", "gateway-c", "gateway-d", "gateway-e"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_included_base_settings_overridden_by_own() {
+        let dir = temp_config_dir("base");
+        fs::write(
+            dir.join("common.toml"),
+            r#"
+                include = ["defaults.toml"]
+                ca_cert_path = "pki/ca.crt"
+                health_check_interval_secs = 15
+                readiness = "connected_peer"
+                log_format = "json"
+
+                [[peers]]
+                node_id = "gateway-c"
+                address = "127.0.0.1:8003"
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.join("defaults.toml"), "health_check_interval_secs = 60
+lsa_interval_secs = 45
+").unwrap();
+        fs::write(
+            dir.join("gateway.toml"),
+            r#"
+                node_id = "gateway-a"
+                listen_port = 8001
+                include = ["common.toml"]
+                lsa_interval_secs = 20
+                readiness = "routing_initialized"
+
+                [[peers]]
+                node_id = "gateway-b"
+                address = "127.0.0.1:8002"
+            "#,
+        )
+        .unwrap();
+
+        let config = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap();
+        // Own values win over included ones, later includes over earlier
+        assert_eq!(config.lsa_interval_secs, 20);
+        assert_eq!(config.health_check_interval_secs, 15);
+        assert_eq!(config.readiness, ReadinessCriteria::RoutingInitialized);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(PathBuf::from(&config.ca_cert_path), dir.join("pki/ca.crt"));
+        // Peers are combined rather than replaced
+        let ids: Vec<&str> = config.peers.iter().map(|p| p.node_id.as_str()).collect();
+        assert_eq!(ids, vec!["gateway-b", "gateway-c"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_include_cycle_rejected() {
+        let dir = temp_config_dir("cycle");
+        fs::write(dir.join("gateway.toml"), "node_id = \"gateway-a\"\nlisten_port = 8001\ninclude = [\"common.toml\"]\n").unwrap();
+        fs::write(dir.join("common.toml"), "include = [\"more.toml\"]\n").unwrap();
+        fs::write(dir.join("more.toml"), "include = [\"common.toml\"]\n").unwrap();
+
+        let message = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap_err().to_string();
+        assert!(message.contains("include cycle"), "{}", message);
+        assert!(message.contains("common.toml -> "), "{}", message);
+
+        // Including itself is a cycle too
+        fs::write(dir.join("gateway.toml"), "node_id = \"gateway-a\"\nlisten_port = 8001\ninclude = [\"gateway.toml\"]\n").unwrap();
+        assert!(GatewayConfig::from_file(dir.join("gateway.toml")).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_relative_cert_paths_resolve_against_config_dir() {
+        let dir = temp_config_dir("paths");
+        fs::write(
+            dir.join("gateway.toml"),
+            "node_id = \"gateway-a\"\nlisten_port = 8001\nca_cert_path = \"./pki/ca.crt\"\nkey_path = \"/etc/mesh/gateway-a.key\"\n",
+        )
+        .unwrap();
+
+        let config = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap();
+        assert_eq!(PathBuf::from(&config.cert_path), dir.join("certs/gateway-a.crt"));
+        assert_eq!(PathBuf::from(&config.ca_cert_path), dir.join("pki/ca.crt"));
+        // Absolute paths are used as written
+        assert_eq!(config.key_path, "/etc/mesh/gateway-a.key");
+
+        // base_dir is relative to the config file's directory too
+        fs::write(dir.join("gateway.toml"), "node_id = \"gateway-a\"\nlisten_port = 8001\nbase_dir = \"..\"\n").unwrap();
+        let config = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap();
+        assert_eq!(PathBuf::from(&config.cert_path), dir.parent().unwrap().join("certs/gateway-a.crt"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_duplicate_peer_across_files_rejected() {
+        let dir = temp_config_dir("duplicate");
+        fs::write(
+            dir.join("gateway.toml"),
+            r#"
+                node_id = "gateway-a"
+                listen_port = 8001
+                peers_dir = "peers.d"
+
+                [[peers]]
+                node_id = "gateway-b"
+                address = "127.0.0.1:8002"
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.join("peers.d/b.toml"), "[[peers]]\nnode_id = \"gateway-b\"\naddress = \"10.0.0.2:8002\"\n").unwrap();
+
+        let err = GatewayConfig::from_file(dir.join("gateway.toml")).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("gateway-b"));
+        assert!(message.contains("b.toml"));
+
+        let _ = fs::remove_dir_all(&dir);
     }
 }
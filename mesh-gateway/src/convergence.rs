@@ -0,0 +1,179 @@
+use crate::types::ConvergenceResponse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Computed route to every known destination (destination -> path)
+pub type RouteSnapshot = HashMap<String, Vec<String>>;
+
+/// Measures how long routing takes to settle after a topology change
+///
+/// The first accepted LSA after a quiet period starts a burst. Every accepted
+/// LSA in the burst is compared against the previous routing decisions, and
+/// the burst is considered converged once no LSA has arrived for the quiet
+/// period. The convergence time is the span from the first LSA of the burst
+/// to the last one that actually changed a route.
+#[derive(Clone)]
+pub struct ConvergenceTracker {
+    inner: Arc<Mutex<TrackerInner>>,
+    quiet_period: Duration,
+}
+
+#[derive(Default)]
+struct TrackerInner {
+    /// Routing decisions after the most recent topology change
+    routes: RouteSnapshot,
+
+    /// Start of the burst currently in progress
+    burst_start: Option<Instant>,
+
+    /// Most recent topology change in the current burst
+    last_event: Option<Instant>,
+
+    /// Most recent change in the current burst that altered a route
+    last_route_change: Option<Instant>,
+
+    /// Topology changes seen in the current burst
+    burst_events: u64,
+
+    /// Duration and event count of the last completed burst
+    last_convergence: Option<(Duration, u64)>,
+}
+
+impl ConvergenceTracker {
+    /// Create a tracker that considers a burst over after `quiet_period` without changes
+    pub fn new(quiet_period: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(TrackerInner::default())),
+            quiet_period,
+        }
+    }
+
+    /// Record an accepted topology change along with the routes it produced
+    pub fn record_change(&self, routes: RouteSnapshot) {
+        self.record_change_at(routes, Instant::now());
+    }
+
+    /// Duration of the most recently completed convergence, if any
+    pub fn last_convergence(&self) -> Option<Duration> {
+        let mut inner = self.inner.lock().unwrap();
+        self.close_burst_if_quiet(&mut inner, Instant::now());
+        inner.last_convergence.map(|(duration, _)| duration)
+    }
+
+    /// Current convergence state for the API
+    pub fn status(&self) -> ConvergenceResponse {
+        self.status_at(Instant::now())
+    }
+
+    fn record_change_at(&self, routes: RouteSnapshot, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        self.close_burst_if_quiet(&mut inner, now);
+
+        if inner.burst_start.is_none() {
+            inner.burst_start = Some(now);
+        }
+        inner.last_event = Some(now);
+        inner.burst_events += 1;
+
+        if routes != inner.routes {
+            inner.last_route_change = Some(now);
+            inner.routes = routes;
+        }
+    }
+
+    fn status_at(&self, now: Instant) -> ConvergenceResponse {
+        let mut inner = self.inner.lock().unwrap();
+        self.close_burst_if_quiet(&mut inner, now);
+
+        ConvergenceResponse {
+            converged: inner.burst_start.is_none(),
+            last_convergence_ms: inner
+                .last_convergence
+                .map(|(duration, _)| duration.as_millis() as u64),
+            last_burst_lsa_count: inner.last_convergence.map(|(_, events)| events).unwrap_or(0),
+            quiet_period_ms: self.quiet_period.as_millis() as u64,
+        }
+    }
+
+    /// Finalize the current burst if nothing has changed for the quiet period
+    fn close_burst_if_quiet(&self, inner: &mut TrackerInner, now: Instant) {
+        let (Some(start), Some(last_event)) = (inner.burst_start, inner.last_event) else {
+            return;
+        };
+
+        if now.saturating_duration_since(last_event) < self.quiet_period {
+            return;
+        }
+
+        let converged_at = inner.last_route_change.filter(|t| *t >= start).unwrap_or(start);
+        let duration = converged_at.saturating_duration_since(start);
+        inner.last_convergence = Some((duration, inner.burst_events));
+
+        tracing::info!(
+            "Topology converged in {}ms after {} LSA update(s)",
+            duration.as_millis(),
+            inner.burst_events
+        );
+
+        inner.burst_start = None;
+        inner.last_event = None;
+        inner.last_route_change = None;
+        inner.burst_events = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(entries: &[(&str, &[&str])]) -> RouteSnapshot {
+        entries
+            .iter()
+            .map(|(dest, path)| (dest.to_string(), path.iter().map(|n| n.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_convergence_measured_to_last_route_change() {
+        let tracker = ConvergenceTracker::new(Duration::from_secs(2));
+        let t0 = Instant::now();
+
+        tracker.record_change_at(routes(&[("gateway-c", &["gateway-b", "gateway-c"])]), t0);
+        tracker.record_change_at(routes(&[("gateway-c", &["gateway-c"])]), t0 + Duration::from_millis(300));
+        // Later LSA in the burst that doesn't change any route
+        tracker.record_change_at(routes(&[("gateway-c", &["gateway-c"])]), t0 + Duration::from_millis(900));
+
+        // Still within the quiet period: burst in progress
+        let status = tracker.status_at(t0 + Duration::from_secs(1));
+        assert!(!status.converged);
+        assert_eq!(status.last_convergence_ms, None);
+
+        let status = tracker.status_at(t0 + Duration::from_secs(5));
+        assert!(status.converged);
+        assert_eq!(status.last_convergence_ms, Some(300));
+        assert_eq!(status.last_burst_lsa_count, 3);
+    }
+
+    #[test]
+    fn test_separate_bursts_measured_independently() {
+        let tracker = ConvergenceTracker::new(Duration::from_secs(1));
+        let t0 = Instant::now();
+
+        tracker.record_change_at(routes(&[("gateway-b", &["gateway-b"])]), t0);
+        tracker.record_change_at(routes(&[("gateway-c", &["gateway-c"])]), t0 + Duration::from_millis(500));
+
+        // A change long after the first burst starts a new one
+        let t1 = t0 + Duration::from_secs(10);
+        tracker.record_change_at(routes(&[("gateway-c", &["gateway-b", "gateway-c"])]), t1);
+
+        let status = tracker.status_at(t1 + Duration::from_millis(100));
+        assert!(!status.converged);
+        assert_eq!(status.last_convergence_ms, Some(500));
+
+        let status = tracker.status_at(t1 + Duration::from_secs(2));
+        assert!(status.converged);
+        assert_eq!(status.last_convergence_ms, Some(0));
+        assert_eq!(status.last_burst_lsa_count, 1);
+    }
+}
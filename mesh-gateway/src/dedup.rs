@@ -0,0 +1,63 @@
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bounded, time-expiring set of recently seen ids
+///
+/// Remembers up to `capacity` ids for `ttl` each, forgetting the least
+/// recently seen one when full. Used to process each message or ack id at
+/// most once per hop.
+#[derive(Clone)]
+pub struct SeenCache {
+    seen: Arc<Mutex<LruCache<String, Instant>>>,
+    ttl: Duration,
+}
+
+impl SeenCache {
+    /// Create a cache holding up to `capacity` ids (at least one) for `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            seen: Arc::new(Mutex::new(LruCache::new(capacity))),
+            ttl,
+        }
+    }
+
+    /// Record `id` as seen, returning true if it wasn't seen within the TTL
+    pub fn first_sighting(&self, id: &str) -> bool {
+        self.first_sighting_at(id, Instant::now())
+    }
+
+    fn first_sighting_at(&self, id: &str, now: Instant) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+
+        let recently_seen = seen
+            .peek(id)
+            .is_some_and(|seen_at| now.saturating_duration_since(*seen_at) < self.ttl);
+        if recently_seen {
+            return false;
+        }
+
+        seen.put(id.to_string(), now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeat_within_ttl_is_not_first_sighting() {
+        let cache = SeenCache::new(10, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(cache.first_sighting_at("m1", start));
+        assert!(!cache.first_sighting_at("m1", start + Duration::from_secs(30)));
+        assert!(cache.first_sighting_at("m2", start + Duration::from_secs(30)));
+
+        // Once the TTL has passed the id counts as new again
+        assert!(cache.first_sighting_at("m1", start + Duration::from_secs(90)));
+    }
+}
@@ -0,0 +1,166 @@
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use rustls::ClientConfig;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::{TcpSocket, TcpStream};
+
+/// Marks forwarded messages of at least `min_priority` with the DSCP value `dscp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DscpClass {
+    pub min_priority: u8,
+    /// Differentiated Services code point, 0-63 (e.g. 46 = Expedited Forwarding)
+    pub dscp: u8,
+}
+
+/// DSCP value for a message of `priority`: that of the class with the highest
+/// `min_priority` not above it, or None if no class covers it
+pub fn dscp_for(classes: &[DscpClass], priority: u8) -> Option<u8> {
+    classes
+        .iter()
+        .filter(|class| class.min_priority <= priority)
+        .max_by_key(|class| class.min_priority)
+        .map(|class| class.dscp)
+}
+
+/// HTTPS client whose connections carry a DSCP mark
+///
+/// reqwest doesn't expose its sockets, so marked traffic goes through a
+/// hyper client over `DscpConnector`. Each mark needs its own connections,
+/// so each class keeps its own pool.
+#[derive(Clone)]
+pub struct MarkedClient {
+    client: hyper::Client<HttpsConnector<DscpConnector>>,
+    /// Time allowed for a whole request, from connecting to reading the response
+    pub request_timeout: Duration,
+}
+
+impl MarkedClient {
+    /// Build a client marking its connections with `dscp`
+    ///
+    /// `tls` supplies the identity and trust; its ALPN protocols are replaced
+    /// according to `http1_only`.
+    pub fn new(tls: ClientConfig, dscp: u8, connect_timeout: Duration, request_timeout: Duration, http1_only: bool) -> Self {
+        let connector = DscpConnector { dscp, connect_timeout };
+        let builder = HttpsConnectorBuilder::new().with_tls_config(tls).https_only();
+        let https = if http1_only {
+            builder.enable_http1().wrap_connector(connector)
+        } else {
+            builder.enable_all_versions().wrap_connector(connector)
+        };
+
+        Self {
+            client: hyper::Client::builder().build(https),
+            request_timeout,
+        }
+    }
+
+    pub fn client(&self) -> &hyper::Client<HttpsConnector<DscpConnector>> {
+        &self.client
+    }
+}
+
+/// Opens TCP connections with the IP TOS byte set to a DSCP value
+///
+/// Only IPv4 connections are marked: there's no portable way to set the
+/// IPv6 traffic class here, so IPv6 peers are reached unmarked. Platforms
+/// without `IP_TOS` (Solaris, illumos and a few others) mark nothing.
+#[derive(Clone)]
+pub struct DscpConnector {
+    dscp: u8,
+    connect_timeout: Duration,
+}
+
+impl Service<Uri> for DscpConnector {
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let dscp = self.dscp;
+        let connect_timeout = self.connect_timeout;
+        Box::pin(async move {
+            let host = uri
+                .host()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("no host in {}", uri)))?
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string();
+            let port = uri.port_u16().unwrap_or(443);
+
+            tokio::time::timeout(connect_timeout, connect(&host, port, dscp))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, format!("connecting to {}:{} timed out", host, port)))?
+        })
+    }
+}
+
+/// Connect to the first address `host` resolves to that accepts
+async fn connect(host: &str, port: u16, dscp: u8) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{} resolved to no addresses", host));
+    for address in tokio::net::lookup_host((host, port)).await? {
+        match connect_marked(address, dscp).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+async fn connect_marked(address: SocketAddr, dscp: u8) -> io::Result<TcpStream> {
+    let socket = match address {
+        SocketAddr::V4(_) => {
+            let socket = TcpSocket::new_v4()?;
+            set_tos(&socket, dscp);
+            socket
+        }
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    let stream = socket.connect(address).await?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/// DSCP is the top six bits of the TOS byte; a failure only costs the mark
+#[cfg(not(any(target_os = "fuchsia", target_os = "redox", target_os = "solaris", target_os = "illumos", target_os = "haiku")))]
+fn set_tos(socket: &TcpSocket, dscp: u8) {
+    if let Err(e) = socket.set_tos(u32::from(dscp) << 2) {
+        tracing::warn!("Failed to set DSCP {} on outbound socket: {}", dscp, e);
+    }
+}
+
+#[cfg(any(target_os = "fuchsia", target_os = "redox", target_os = "solaris", target_os = "illumos", target_os = "haiku"))]
+fn set_tos(_socket: &TcpSocket, _dscp: u8) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dscp_for_picks_highest_class_not_above_priority() {
+        let classes = [
+            DscpClass { min_priority: 8, dscp: 46 },
+            DscpClass { min_priority: 1, dscp: 10 },
+            DscpClass { min_priority: 5, dscp: 34 },
+        ];
+
+        assert_eq!(dscp_for(&classes, 0), None);
+        assert_eq!(dscp_for(&classes, 1), Some(10));
+        assert_eq!(dscp_for(&classes, 4), Some(10));
+        assert_eq!(dscp_for(&classes, 5), Some(34));
+        assert_eq!(dscp_for(&classes, 7), Some(34));
+        assert_eq!(dscp_for(&classes, 8), Some(46));
+        assert_eq!(dscp_for(&classes, 255), Some(46));
+        assert_eq!(dscp_for(&[], 9), None);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::certs::{load_cert, load_private_key};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::pkcs8::DecodePrivateKey;
+use p256::{PublicKey, SecretKey};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// HKDF info string, so keys derived here can't collide with another use of ECDH
+const KEY_INFO: &[u8] = b"mesh-gateway message content v1";
+
+/// Length of an uncompressed SEC1 P-256 point, which prefixes every ciphertext
+const EPHEMERAL_KEY_LEN: usize = 65;
+
+/// Public keys of the nodes messages can be encrypted to, by node_id
+///
+/// Filled from the certificates listed in `recipient_certs` and from the
+/// signer certificates of verified LSAs.
+#[derive(Clone, Default)]
+pub struct Keystore {
+    keys: Arc<RwLock<HashMap<String, PublicKey>>>,
+}
+
+impl Keystore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the certificates in `cert_paths`, keyed by their CommonName
+    pub fn from_cert_files(cert_paths: &[impl AsRef<Path>]) -> Result<Self> {
+        let keystore = Self::new();
+        for path in cert_paths {
+            let cert = load_cert(path)?.remove(0);
+            keystore
+                .add_cert(&cert.0)
+                .with_context(|| format!("Unusable recipient certificate {:?}", path.as_ref()))?;
+        }
+        Ok(keystore)
+    }
+
+    /// Store the public key of a DER certificate under its CommonName
+    /// Returns the node_id it was stored under.
+    pub fn add_cert(&self, cert_der: &[u8]) -> Result<String> {
+        let (_, cert) = X509Certificate::from_der(cert_der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+        let node_id = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .context("Certificate has no CommonName")?
+            .to_string();
+        let public_key = PublicKey::from_sec1_bytes(cert.public_key().subject_public_key.data.as_ref())
+            .map_err(|_| anyhow::anyhow!("Certificate for '{}' doesn't hold a P-256 key", node_id))?;
+
+        self.insert(&node_id, public_key);
+        Ok(node_id)
+    }
+
+    pub fn insert(&self, node_id: &str, public_key: PublicKey) {
+        self.keys.write().unwrap().insert(node_id.to_string(), public_key);
+    }
+
+    pub fn get(&self, node_id: &str) -> Option<PublicKey> {
+        self.keys.read().unwrap().get(node_id).copied()
+    }
+}
+
+/// Encrypt message content so only `recipient` can read it
+///
+/// A fresh ephemeral key is agreed with the recipient's public key (ECDH),
+/// and the shared secret keys AES-256-GCM via HKDF. The output is base64 of
+/// the ephemeral public key followed by the sealed content. The recipient's
+/// node_id is authenticated too, so the blob can't be re-addressed.
+pub fn encrypt_content(recipient_id: &str, recipient: &PublicKey, plaintext: &str) -> Result<String> {
+    let ephemeral = random_secret_key()?;
+    let ephemeral_public = ephemeral.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(ephemeral.to_nonzero_scalar(), recipient.as_affine());
+    let key = content_key(shared.raw_secret_bytes(), ephemeral_public.as_bytes())?;
+
+    let mut sealed = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(single_use_nonce(), Aad::from(recipient_id.as_bytes()), &mut sealed)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt message content"))?;
+
+    let mut blob = ephemeral_public.as_bytes().to_vec();
+    blob.extend_from_slice(&sealed);
+    Ok(BASE64.encode(blob))
+}
+
+/// This node's private key, for decrypting content encrypted to it
+pub struct ContentKey {
+    node_id: String,
+    secret: SecretKey,
+}
+
+impl ContentKey {
+    /// Load the private key belonging to this node's certificate (ECDSA P-256),
+    /// PKCS#8 or SEC1 encoded
+    pub fn from_file(node_id: &str, key_path: impl AsRef<Path>) -> Result<Self> {
+        let key = load_private_key(&key_path)?;
+        let secret = SecretKey::from_pkcs8_der(&key.0)
+            .or_else(|_| SecretKey::from_sec1_der(&key.0))
+            .map_err(|e| anyhow::anyhow!("Unsupported decryption key {:?}: {}", key_path.as_ref(), e))?;
+        Ok(Self {
+            node_id: node_id.to_string(),
+            secret,
+        })
+    }
+
+    /// Decrypt content produced by `encrypt_content` for this node
+    pub fn decrypt(&self, content: &str) -> Result<String> {
+        let blob = BASE64.decode(content).context("Encrypted content is not base64")?;
+        if blob.len() < EPHEMERAL_KEY_LEN {
+            anyhow::bail!("Encrypted content is truncated");
+        }
+        let (ephemeral_public, sealed) = blob.split_at(EPHEMERAL_KEY_LEN);
+        let ephemeral = PublicKey::from_sec1_bytes(ephemeral_public)
+            .map_err(|_| anyhow::anyhow!("Encrypted content has an invalid ephemeral key"))?;
+
+        let shared = p256::ecdh::diffie_hellman(self.secret.to_nonzero_scalar(), ephemeral.as_affine());
+        let key = content_key(shared.raw_secret_bytes(), ephemeral_public)?;
+
+        let mut sealed = sealed.to_vec();
+        let plaintext = key
+            .open_in_place(single_use_nonce(), Aad::from(self.node_id.as_bytes()), &mut sealed)
+            .map_err(|_| anyhow::anyhow!("Content was not encrypted for {} or was altered", self.node_id))?;
+        String::from_utf8(plaintext.to_vec()).context("Decrypted content is not UTF-8")
+    }
+}
+
+/// Derive the AES-256-GCM key for one message from the ECDH shared secret
+fn content_key(shared_secret: &[u8], ephemeral_public: &[u8]) -> Result<LessSafeKey> {
+    let prk = Salt::new(HKDF_SHA256, ephemeral_public).extract(shared_secret);
+    let okm = prk
+        .expand(&[KEY_INFO], &AES_256_GCM)
+        .map_err(|_| anyhow::anyhow!("Failed to derive content key"))?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+/// Every message is sealed under its own ephemeral key, so a fixed nonce
+/// is never reused with the same key
+fn single_use_nonce() -> Nonce {
+    Nonce::assume_unique_for_key([0; 12])
+}
+
+fn random_secret_key() -> Result<SecretKey> {
+    let rng = SystemRandom::new();
+    let mut bytes = [0u8; 32];
+    // Out-of-range scalars are rare enough that retrying is simplest
+    loop {
+        rng.fill(&mut bytes)
+            .map_err(|_| anyhow::anyhow!("Failed to generate an ephemeral key"))?;
+        if let Ok(key) = SecretKey::from_slice(&bytes) {
+            return Ok(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestPki;
+
+    #[test]
+    fn test_content_round_trip() {
+        let pki = TestPki::generate(&["gateway-b", "gateway-c"]);
+        let keystore = Keystore::from_cert_files(&[pki.cert_path("gateway-c")]).unwrap();
+        let recipient = keystore.get("gateway-c").unwrap();
+
+        let ciphertext = encrypt_content("gateway-c", &recipient, "hello gateway-c").unwrap();
+        assert!(!ciphertext.contains("hello"));
+
+        let key = ContentKey::from_file("gateway-c", pki.key_path("gateway-c")).unwrap();
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), "hello gateway-c");
+
+        // Another node can't read it, even with the right node_id claimed
+        let other = ContentKey::from_file("gateway-c", pki.key_path("gateway-b")).unwrap();
+        assert!(other.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_readdressed_content_rejected() {
+        let pki = TestPki::generate(&["gateway-c"]);
+        let keystore = Keystore::from_cert_files(&[pki.cert_path("gateway-c")]).unwrap();
+
+        // Sealed for a different node_id than the key's owner
+        let ciphertext = encrypt_content("gateway-d", &keystore.get("gateway-c").unwrap(), "hello").unwrap();
+        let key = ContentKey::from_file("gateway-c", pki.key_path("gateway-c")).unwrap();
+        assert!(key.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_sec1_key_decrypts() {
+        let keystore = Keystore::from_cert_files(&["testdata/ec-sec1.crt"]).unwrap();
+        let recipient = keystore.get("ec-sec1").unwrap();
+
+        let ciphertext = encrypt_content("ec-sec1", &recipient, "hello").unwrap();
+        let key = ContentKey::from_file("ec-sec1", "testdata/ec-sec1.key").unwrap();
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), "hello");
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What to do with a newly accepted LSA that is due for re-flooding
+#[derive(Debug, PartialEq, Eq)]
+pub enum FloodDecision {
+    /// Flood it right away
+    Now,
+    /// Held back; flood after this long if `take_pending` says so
+    Later(Duration),
+    /// Held back along with an LSA already waiting, whose timer will flood
+    /// the newest of them
+    Coalesced,
+}
+
+/// Limits how often each originator's LSAs are re-flooded
+///
+/// The first LSA from an originator floods immediately. Any that arrive
+/// within the minimum interval after that are coalesced into one flood once
+/// the interval is up; the routing table keeps the newest of them for it.
+#[derive(Clone, Default)]
+pub struct FloodThrottle {
+    originators: Arc<Mutex<HashMap<String, Slot>>>,
+}
+
+struct Slot {
+    flooded_at: Instant,
+    /// Whether a held-back flood is waiting for the interval to pass
+    pending: bool,
+}
+
+impl FloodThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decide whether a new LSA from `originator` floods now or later
+    pub fn admit(&self, originator: &str, interval: Duration) -> FloodDecision {
+        let now = Instant::now();
+        let mut originators = self.originators.lock().unwrap();
+
+        let Some(slot) = originators.get_mut(originator) else {
+            originators.insert(originator.to_string(), Slot { flooded_at: now, pending: false });
+            return FloodDecision::Now;
+        };
+
+        let due = slot.flooded_at + interval;
+        if now >= due && !slot.pending {
+            slot.flooded_at = now;
+            return FloodDecision::Now;
+        }
+
+        if std::mem::replace(&mut slot.pending, true) {
+            FloodDecision::Coalesced
+        } else {
+            FloodDecision::Later(due.saturating_duration_since(now))
+        }
+    }
+
+    /// Whether a held-back flood from `originator` is still due, counting it
+    /// as flooded now
+    pub fn take_pending(&self, originator: &str) -> bool {
+        let mut originators = self.originators.lock().unwrap();
+        let Some(slot) = originators.get_mut(originator) else {
+            return false;
+        };
+        if !std::mem::take(&mut slot.pending) {
+            return false;
+        }
+        slot.flooded_at = Instant::now();
+        true
+    }
+}
@@ -0,0 +1,150 @@
+use crate::types::ReceiveMessageRequest;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Messages accepted for forwarding but not yet sent, highest priority first
+///
+/// Messages of equal priority come out in the order they were pushed. Used
+/// with `queued_forwarding`, where the handlers only accept a message and a
+/// background worker does the forwarding.
+#[derive(Clone, Default)]
+pub struct ForwardQueue {
+    inner: Arc<Mutex<QueueInner>>,
+    notify: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    heap: BinaryHeap<QueuedMessage>,
+    /// Push counter, so equal priorities are served first come, first served
+    pushed: u64,
+}
+
+struct QueuedMessage {
+    priority: u8,
+    sequence: u64,
+    message: ReceiveMessageRequest,
+}
+
+impl Ord for QueuedMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Max-heap: higher priority first, then the earlier push
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for QueuedMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedMessage {}
+
+impl ForwardQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `message` behind any waiting messages of the same or higher priority
+    pub fn push(&self, message: ReceiveMessageRequest) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let sequence = inner.pushed;
+            inner.pushed += 1;
+            inner.heap.push(QueuedMessage {
+                priority: message.priority,
+                sequence,
+                message,
+            });
+        }
+        self.notify.notify_one();
+    }
+
+    /// Take the highest-priority message, if any is waiting
+    pub fn pop(&self) -> Option<ReceiveMessageRequest> {
+        self.inner.lock().unwrap().heap.pop().map(|queued| queued.message)
+    }
+
+    /// Wait for a message and take the highest-priority one
+    pub async fn next(&self) -> ReceiveMessageRequest {
+        loop {
+            if let Some(message) = self.pop() {
+                return message;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DEFAULT_MESSAGE_TTL;
+    use std::time::Duration;
+
+    fn message(message_id: &str, priority: u8) -> ReceiveMessageRequest {
+        ReceiveMessageRequest {
+            from: "gateway-a".to_string(),
+            to: "gateway-b".to_string(),
+            content: message_id.to_string(),
+            route: vec!["gateway-a".to_string()],
+            ttl: DEFAULT_MESSAGE_TTL,
+            message_id: message_id.to_string(),
+            timestamp: None,
+            nonce: String::new(),
+            priority,
+            encrypted: false,
+            binary: false,
+            compression: None,
+            explicit_route: None,
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_high_priority_dequeued_before_earlier_low_priority() {
+        let queue = ForwardQueue::new();
+        queue.push(message("bulk-1", 0));
+        queue.push(message("bulk-2", 0));
+        queue.push(message("control", 9));
+        queue.push(message("bulk-3", 0));
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop()).map(|m| m.message_id).collect();
+        assert_eq!(order, vec!["control", "bulk-1", "bulk-2", "bulk-3"]);
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_next_waits_for_a_push() {
+        let queue = ForwardQueue::new();
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.next().await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.push(message("m1", 0));
+
+        let message = tokio::time::timeout(Duration::from_secs(5), waiter).await.unwrap().unwrap();
+        assert_eq!(message.message_id, "m1");
+    }
+}
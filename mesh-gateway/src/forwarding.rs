@@ -0,0 +1,837 @@
+use crate::client::{ConnectionStats, PeerClients};
+use crate::config::{PeerConfig, PeerProtocol};
+use crate::dscp::MarkedClient;
+use crate::wire::{self, WireEncoding, WireError};
+use crate::types::{DeadLetter, ErrorDetail, ErrorKind, ReceiveMessageRequest, SendMessageResponse, CORRELATION_ID_HEADER};
+use reqwest::{Client, StatusCode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+
+/// Errors that can occur while forwarding a message to the next hop
+#[derive(Debug, Error)]
+pub enum ForwardError {
+    #[error("request failed: {0}")]
+    Request(reqwest::Error),
+
+    /// A request over a DSCP-marked connection failed
+    #[error("request failed: {0}")]
+    Marked(hyper::Error),
+
+    /// A request over a DSCP-marked connection took too long
+    #[error("request timed out")]
+    MarkedTimeout,
+
+    #[error("HTTP {0}")]
+    Status(StatusCode),
+
+    #[error("failed to parse response: {0}")]
+    Parse(WireError),
+
+    #[error("send queue for {0} is closed")]
+    QueueClosed(String),
+
+    /// The peer's send queue already holds `ordered_queue_depth` messages
+    #[error("send queue for {0} is full")]
+    QueueFull(String),
+
+    #[error("circuit to {0} is open")]
+    CircuitOpen(String),
+}
+
+impl ForwardError {
+    /// Whether a later attempt might succeed: the peer couldn't be reached,
+    /// timed out, or reported a server error. Rejections (4xx) are final.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ForwardError::Request(e) => e.is_connect() || e.is_timeout(),
+            ForwardError::Marked(e) => e.is_connect(),
+            ForwardError::MarkedTimeout => true,
+            ForwardError::Status(status) => status.is_server_error(),
+            ForwardError::Parse(_) | ForwardError::QueueClosed(_) | ForwardError::QueueFull(_) | ForwardError::CircuitOpen(_) => false,
+        }
+    }
+
+    /// Status reported for a message that couldn't be forwarded
+    pub fn status(&self) -> &'static str {
+        match self {
+            ForwardError::CircuitOpen(_) => "circuit_open",
+            ForwardError::QueueFull(_) => "queue_full",
+            _ => "failed",
+        }
+    }
+
+    /// Describe the failure for the sender of the message
+    pub fn detail(&self) -> ErrorDetail {
+        let kind = match self {
+            ForwardError::Request(e) if e.is_timeout() => ErrorKind::Timeout,
+            ForwardError::MarkedTimeout => ErrorKind::Timeout,
+            ForwardError::Request(_) | ForwardError::Marked(_) | ForwardError::QueueClosed(_) => ErrorKind::Connection,
+            ForwardError::Status(_) => ErrorKind::NextHopStatus,
+            ForwardError::Parse(_) => ErrorKind::InvalidResponse,
+            ForwardError::CircuitOpen(_) => ErrorKind::CircuitOpen,
+            ForwardError::QueueFull(_) => ErrorKind::QueueFull,
+        };
+        ErrorDetail::new(kind, self.to_string())
+    }
+}
+
+/// Result of a single forward attempt
+pub type ForwardResult = Result<SendMessageResponse, ForwardError>;
+
+/// How often to retry a forward that failed transiently
+///
+/// Retry `n` (counting from 0) waits `base_delay * 2^n`, so a 100ms base
+/// gives 100ms, 200ms, 400ms, ... The default never retries.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (0-based)
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << retry.min(16))
+    }
+}
+
+/// Caps the requests to peers in flight at once, shared by message
+/// forwarding, acks, and flooding and broadcasting LSAs
+///
+/// Senders wait for a permit before starting a request, so a burst queues
+/// up instead of opening (and spawning tasks for) an unbounded number.
+#[derive(Clone, Default)]
+pub struct OutboundLimit {
+    /// None = unlimited
+    permits: Option<Arc<Semaphore>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+/// Held for the duration of one outbound request
+pub struct OutboundPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl OutboundLimit {
+    /// Allow at most `max` requests at once (None = unlimited)
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            permits: max.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Wait until another request may start
+    pub async fn acquire(&self) -> OutboundPermit {
+        let permit = match &self.permits {
+            // The semaphore is never closed
+            Some(permits) => Some(permits.clone().acquire_owned().await.expect("outbound semaphore closed")),
+            None => None,
+        };
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        OutboundPermit {
+            _permit: permit,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+
+    /// Requests currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for OutboundPermit {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A message waiting in a per-peer send queue
+struct QueuedMessage {
+    request: ReceiveMessageRequest,
+    reply: oneshot::Sender<ForwardResult>,
+}
+
+/// Messages each peer's send queue holds unless configured otherwise
+pub const DEFAULT_QUEUE_DEPTH: usize = 1_000;
+
+/// Forwards messages to next-hop peers over the mTLS client
+///
+/// By default every forward is an independent request, so concurrent messages
+/// to the same peer may arrive in any order. In ordered mode each peer gets a
+/// single worker task draining an mpsc queue, which guarantees messages to the
+/// same next hop are sent (and acknowledged) in submission order. Each queue
+/// holds at most `queue_depth` messages; beyond that forwards fail fast.
+///
+/// Each peer address is sent to with the client matching its configured
+/// protocol preference; unknown addresses use the h2-capable client.
+#[derive(Clone)]
+pub struct Forwarder {
+    clients: PeerClients,
+    /// Map of peer address -> configured protocol preference
+    protocols: Arc<RwLock<HashMap<String, PeerProtocol>>>,
+    ordered: bool,
+    retry: RetryPolicy,
+    encoding: WireEncoding,
+    outbound: OutboundLimit,
+    queue_depth: usize,
+    /// Map of peer address -> sender for that peer's queue
+    queues: Arc<Mutex<HashMap<String, mpsc::Sender<QueuedMessage>>>>,
+}
+
+impl Forwarder {
+    /// Create a new forwarder using the given HTTP client
+    pub fn new(client: Client, ordered: bool) -> Self {
+        Self {
+            clients: PeerClients::from_client(client),
+            protocols: Arc::new(RwLock::new(HashMap::new())),
+            ordered,
+            retry: RetryPolicy::default(),
+            encoding: WireEncoding::default(),
+            outbound: OutboundLimit::default(),
+            queue_depth: DEFAULT_QUEUE_DEPTH,
+            queues: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Retry transient failures according to `policy`
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Send messages in `encoding` (JSON by default)
+    pub fn with_wire_encoding(mut self, encoding: WireEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Wait for a permit from `limit` before each request
+    pub fn with_outbound_limit(mut self, limit: OutboundLimit) -> Self {
+        self.outbound = limit;
+        self
+    }
+
+    /// Hold at most `depth` messages in each peer's send queue (ordered mode)
+    pub fn with_queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth.max(1);
+        self
+    }
+
+    /// Select clients per peer according to each peer's configured protocol
+    pub fn with_peer_protocols(mut self, clients: PeerClients, peers: &[PeerConfig]) -> Self {
+        self.clients = clients;
+        self.update_peer_protocols(peers);
+        self
+    }
+
+    /// Replace the per-peer protocol preferences (e.g. after a config reload)
+    ///
+    /// Send queues for peers whose protocol changed are dropped so their next
+    /// message starts a worker with the right client.
+    pub fn update_peer_protocols(&self, peers: &[PeerConfig]) {
+        let protocols: HashMap<String, PeerProtocol> = peers
+            .iter()
+            .map(|peer| (peer.address.clone(), peer.protocol))
+            .collect();
+
+        let mut current = self.protocols.write().unwrap();
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|address, _| current.get(address) == protocols.get(address));
+        *current = protocols;
+    }
+
+    /// Protocol preference of the peer at `address`
+    fn protocol_for(&self, address: &str) -> PeerProtocol {
+        self.protocols
+            .read()
+            .unwrap()
+            .get(address)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Encoding messages are sent in
+    pub fn wire_encoding(&self) -> WireEncoding {
+        self.encoding
+    }
+
+    /// Whether messages are serialized per next hop
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+
+    /// Forward a message to the peer at `address` and wait for its response
+    pub async fn forward(&self, address: &str, request: &ReceiveMessageRequest) -> ForwardResult {
+        if !self.ordered {
+            return post_with_retry(&self.clients, self.protocol_for(address), &self.outbound, address, request, self.retry, self.encoding).await;
+        }
+
+        self.enqueue(address, request.clone())?
+            .await
+            .map_err(|_| ForwardError::QueueClosed(address.to_string()))?
+    }
+
+    /// Place a message on the peer's send queue without waiting for it to be sent
+    ///
+    /// The message is queued before this returns, so successive calls define
+    /// the delivery order. The returned receiver resolves once the peer responds.
+    /// Fails with `QueueFull` if the peer's queue is already at its depth.
+    pub fn enqueue(&self, address: &str, request: ReceiveMessageRequest) -> Result<oneshot::Receiver<ForwardResult>, ForwardError> {
+        let (reply, response) = oneshot::channel();
+        let mut queued = QueuedMessage { request, reply };
+
+        let mut queues = self.queues.lock().unwrap();
+        loop {
+            let sender = queues
+                .entry(address.to_string())
+                .or_insert_with(|| {
+                    spawn_peer_worker(
+                        self.clients.clone(),
+                        self.protocol_for(address),
+                        self.outbound.clone(),
+                        address.to_string(),
+                        self.retry,
+                        self.encoding,
+                        self.queue_depth,
+                    )
+                });
+
+            match sender.try_send(queued) {
+                Ok(()) => break,
+                Err(mpsc::error::TrySendError::Full(_)) => return Err(ForwardError::QueueFull(address.to_string())),
+                Err(mpsc::error::TrySendError::Closed(returned)) => {
+                    // Worker is gone (runtime shut down); replace it and retry
+                    queued = returned;
+                    queues.remove(address);
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Connections opened and reused by the forwarding clients
+    pub fn connection_stats(&self) -> &ConnectionStats {
+        self.clients.stats()
+    }
+
+    /// Open a connection to each peer ahead of the first message by
+    /// health-checking it, so the TLS handshake is already done when
+    /// forwarding starts; returns how many peers answered
+    pub async fn warm_up(&self, addresses: &[String]) -> usize {
+        let mut checks = JoinSet::new();
+        for address in addresses {
+            let client = self.clients.for_protocol(self.protocol_for(address)).clone();
+            let stats = self.clients.stats().clone();
+            let address = address.clone();
+            checks.spawn(async move {
+                stats.record_request();
+                let result = client.get(format!("https://{}/health", address)).send().await;
+                match result {
+                    Ok(response) if response.status().is_success() => true,
+                    Ok(response) => {
+                        tracing::warn!("Warm-up health check of {} returned HTTP {}", address, response.status());
+                        false
+                    }
+                    Err(e) => {
+                        tracing::warn!("Warm-up connection to {} failed: {}", address, e);
+                        false
+                    }
+                }
+            });
+        }
+
+        let mut warmed = 0;
+        while let Some(result) = checks.join_next().await {
+            if matches!(result, Ok(true)) {
+                warmed += 1;
+            }
+        }
+        warmed
+    }
+}
+
+/// Spawn the single task that sends queued messages to one peer in order
+fn spawn_peer_worker(
+    clients: PeerClients,
+    protocol: PeerProtocol,
+    outbound: OutboundLimit,
+    address: String,
+    retry: RetryPolicy,
+    encoding: WireEncoding,
+    depth: usize,
+) -> mpsc::Sender<QueuedMessage> {
+    let (sender, mut receiver) = mpsc::channel::<QueuedMessage>(depth);
+
+    tokio::spawn(async move {
+        while let Some(queued) = receiver.recv().await {
+            // Retrying in the worker holds back later messages, keeping the order
+            let result = post_with_retry(&clients, protocol, &outbound, &address, &queued.request, retry, encoding).await;
+            // The caller may have given up waiting; that's fine
+            let _ = queued.reply.send(result);
+        }
+    });
+
+    sender
+}
+
+/// Best-effort delivery of a dropped message to the dead-letter sink
+///
+/// Runs in the background so the drop decision never waits on the sink;
+/// failures are only logged since there is nowhere further to report them.
+pub fn send_dead_letter(client: &Client, url: &str, letter: DeadLetter) {
+    let client = client.clone();
+    let url = url.to_string();
+
+    tokio::spawn(async move {
+        match client.post(&url).json(&letter).send().await {
+            Ok(response) if response.status().is_success() => {
+                tracing::debug!("Dead-lettered message for {} ({})", letter.to, letter.reason);
+            }
+            Ok(response) => {
+                tracing::warn!("Dead-letter sink {} returned HTTP {}", url, response.status());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to reach dead-letter sink {}: {}", url, e);
+            }
+        }
+    });
+}
+
+/// POST a message, retrying transient failures with exponential backoff
+///
+/// Messages whose priority falls in a DSCP class go over that class's marked client.
+async fn post_with_retry(
+    clients: &PeerClients,
+    protocol: PeerProtocol,
+    outbound: &OutboundLimit,
+    address: &str,
+    request: &ReceiveMessageRequest,
+    policy: RetryPolicy,
+    encoding: WireEncoding,
+) -> ForwardResult {
+    let mut retry = 0;
+    loop {
+        let permit = outbound.acquire().await;
+        clients.stats().record_request();
+        let result = match clients.marked_for(request.priority, protocol) {
+            Some(marked) => post_marked_message(marked, address, request, encoding).await,
+            None => post_message(clients.for_protocol(protocol), address, request, encoding).await,
+        };
+        // Backing off doesn't hold up other requests
+        drop(permit);
+        match result {
+            Err(e) if e.is_retryable() && retry < policy.max_retries => {
+                let delay = policy.delay(retry);
+                tracing::warn!("Forward to {} failed ({}), retrying in {:?}", address, e, delay);
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// POST a message to a peer's /message/receive endpoint and parse its response
+async fn post_message(client: &Client, address: &str, request: &ReceiveMessageRequest, encoding: WireEncoding) -> ForwardResult {
+    // Note: peer addresses are validated in config.rs to be in "host:port" format
+    // without protocol prefix, so this URL construction is safe
+    let url = format!("https://{}/message/receive", address);
+
+    let mut post = encoding.encode(client.post(&url), request);
+    if let Some(correlation_id) = &request.correlation_id {
+        post = post.header(CORRELATION_ID_HEADER, correlation_id);
+    }
+    let response = post.send().await.map_err(ForwardError::Request)?;
+
+    if !response.status().is_success() {
+        return Err(ForwardError::Status(response.status()));
+    }
+
+    wire::read_body::<SendMessageResponse>(response)
+        .await
+        .map_err(ForwardError::Parse)
+}
+
+/// `post_message` over a DSCP-marked connection
+async fn post_marked_message(marked: &MarkedClient, address: &str, request: &ReceiveMessageRequest, encoding: WireEncoding) -> ForwardResult {
+    let (content_type, body) = encoding.body(request).expect("ReceiveMessageRequest serializes to JSON");
+    let mut post = hyper::Request::post(format!("https://{}/message/receive", address))
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .header(hyper::header::ACCEPT, encoding.content_type());
+    if let Some(correlation_id) = &request.correlation_id {
+        post = post.header(CORRELATION_ID_HEADER, correlation_id);
+    }
+    // Peer addresses are validated as host:port, so the request is well-formed
+    let post = post.body(hyper::Body::from(body)).expect("valid message request");
+
+    let exchange = async {
+        let response = marked.client().request(post).await?;
+        let status = response.status();
+        let content_type = response.headers().get(hyper::header::CONTENT_TYPE).cloned();
+        let bytes = hyper::body::to_bytes(response.into_body()).await?;
+        Ok::<_, hyper::Error>((status, content_type, bytes))
+    };
+    let (status, content_type, bytes) = tokio::time::timeout(marked.request_timeout, exchange)
+        .await
+        .map_err(|_| ForwardError::MarkedTimeout)?
+        .map_err(ForwardError::Marked)?;
+
+    if !status.is_success() {
+        return Err(ForwardError::Status(status));
+    }
+
+    wire::decode_body(content_type.as_ref(), &bytes).map_err(ForwardError::Parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientTimeouts;
+    use crate::config::PeerNameVerification;
+    use crate::test_util::TestPki;
+    use crate::types::DEFAULT_MESSAGE_TTL;
+    use axum::{extract::State, routing::post, Json, Router};
+    use std::time::Duration;
+
+    /// Mock peer that records received message contents in arrival order.
+    /// Earlier messages are delayed longer so unordered sends would overtake them.
+    async fn recording_peer(pki: &TestPki, received: Arc<Mutex<Vec<String>>>) -> String {
+        async fn receive(
+            State(received): State<Arc<Mutex<Vec<String>>>>,
+            Json(request): Json<ReceiveMessageRequest>,
+        ) -> Json<SendMessageResponse> {
+            let index: u64 = request.content.parse().unwrap();
+            tokio::time::sleep(Duration::from_millis(50u64.saturating_sub(index * 5))).await;
+            received.lock().unwrap().push(request.content.clone());
+            Json(SendMessageResponse {
+                status: "delivered".to_string(),
+                route: request.route,
+                message_id: None,
+                error: None,
+            })
+        }
+
+        let app = Router::new()
+            .route("/message/receive", post(receive))
+            .with_state(received);
+
+        pki.serve("gateway-b", app).await.to_string()
+    }
+
+    fn message(index: usize) -> ReceiveMessageRequest {
+        ReceiveMessageRequest {
+            from: "gateway-a".to_string(),
+            to: "gateway-b".to_string(),
+            content: index.to_string(),
+            route: vec!["gateway-a".to_string()],
+            ttl: DEFAULT_MESSAGE_TTL,
+            message_id: index.to_string(),
+            timestamp: Some(std::time::SystemTime::now()),
+            nonce: index.to_string(),
+            priority: 0,
+            encrypted: false,
+            binary: false,
+            compression: None,
+            explicit_route: None,
+            correlation_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ordered_forwarding_preserves_submission_order() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let address = recording_peer(&pki, received.clone()).await;
+
+        let forwarder = Forwarder::new(pki.client("gateway-a"), true);
+        assert!(forwarder.is_ordered());
+
+        let pending: Vec<_> = (0..10)
+            .map(|i| forwarder.enqueue(&address, message(i)).unwrap())
+            .collect();
+
+        for response in pending {
+            let response = response.await.unwrap().unwrap();
+            assert_eq!(response.status, "delivered");
+        }
+
+        let expected: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        assert_eq!(*received.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_full_send_queue_fails_fast() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let address = recording_peer(&pki, received.clone()).await;
+
+        let forwarder = Forwarder::new(pki.client("gateway-a"), true).with_queue_depth(2);
+
+        // The worker doesn't run until this task yields, so nothing drains yet
+        let first = forwarder.enqueue(&address, message(0)).unwrap();
+        let second = forwarder.enqueue(&address, message(1)).unwrap();
+        let full = forwarder.enqueue(&address, message(2)).unwrap_err();
+        assert!(matches!(full, ForwardError::QueueFull(_)));
+        assert_eq!(full.status(), "queue_full");
+        assert_eq!(full.detail().kind, ErrorKind::QueueFull);
+
+        assert_eq!(first.await.unwrap().unwrap().status, "delivered");
+        assert_eq!(second.await.unwrap().unwrap().status, "delivered");
+        assert_eq!(*received.lock().unwrap(), vec!["0", "1"]);
+
+        // Once drained, the queue takes messages again
+        assert_eq!(forwarder.forward(&address, &message(3)).await.unwrap().status, "delivered");
+    }
+
+    #[tokio::test]
+    async fn test_outbound_limit_caps_concurrent_forwards() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+
+        // Mock peer that tracks how many requests it is handling at once
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (active_in, peak_in) = (active.clone(), peak.clone());
+        let app = Router::new().route(
+            "/message/receive",
+            post(move |Json(request): Json<ReceiveMessageRequest>| {
+                let (active, peak) = (active_in.clone(), peak_in.clone());
+                async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    Json(SendMessageResponse {
+                        status: "delivered".to_string(),
+                        route: request.route,
+                        message_id: None,
+                        error: None,
+                    })
+                }
+            }),
+        );
+        let address = pki.serve("gateway-b", app).await.to_string();
+
+        let limit = OutboundLimit::new(Some(3));
+        let forwarder = Forwarder::new(pki.client("gateway-a"), false).with_outbound_limit(limit.clone());
+        let burst: Vec<_> = (0..20)
+            .map(|i| {
+                let forwarder = forwarder.clone();
+                let address = address.clone();
+                tokio::spawn(async move { forwarder.forward(&address, &message(i)).await })
+            })
+            .collect();
+        for forward in burst {
+            assert_eq!(forward.await.unwrap().unwrap().status, "delivered");
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(limit.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unordered_forward_reports_connection_error() {
+        let pki = TestPki::generate(&["gateway-a"]);
+        let forwarder = Forwarder::new(pki.client("gateway-a"), false);
+
+        // Nothing is listening on port 1
+        let result = forwarder.forward("127.0.0.1:1", &message(0)).await;
+        assert!(matches!(result, Err(ForwardError::Request(_))));
+    }
+
+    /// Mock peer that answers with each of `statuses` in turn, then delivers
+    async fn flaky_peer(pki: &TestPki, statuses: Vec<StatusCode>, attempts: Arc<Mutex<usize>>) -> String {
+        let app = Router::new().route(
+            "/message/receive",
+            post(move |Json(request): Json<ReceiveMessageRequest>| {
+                let attempts = attempts.clone();
+                let statuses = statuses.clone();
+                async move {
+                    let attempt = {
+                        let mut attempts = attempts.lock().unwrap();
+                        *attempts += 1;
+                        *attempts - 1
+                    };
+                    if let Some(status) = statuses.get(attempt) {
+                        return Err(*status);
+                    }
+                    Ok(Json(SendMessageResponse {
+                        status: "delivered".to_string(),
+                        route: request.route,
+                        message_id: None,
+                        error: None,
+                    }))
+                }
+            }),
+        );
+
+        pki.serve("gateway-b", app).await.to_string()
+    }
+
+    fn retry_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_doubles() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+        };
+        let delays: Vec<_> = (0..3).map(|retry| policy.delay(retry).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400]);
+    }
+
+    #[tokio::test]
+    async fn test_forward_retries_until_peer_recovers() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let attempts = Arc::new(Mutex::new(0));
+        let unavailable = vec![StatusCode::SERVICE_UNAVAILABLE; 2];
+        let address = flaky_peer(&pki, unavailable, attempts.clone()).await;
+
+        for ordered in [false, true] {
+            *attempts.lock().unwrap() = 0;
+            let forwarder = Forwarder::new(pki.client("gateway-a"), ordered).with_retry(retry_policy(3));
+            let response = forwarder.forward(&address, &message(0)).await.unwrap();
+            assert_eq!(response.status, "delivered");
+            assert_eq!(*attempts.lock().unwrap(), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_gives_up_after_max_retries_and_on_rejection() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let attempts = Arc::new(Mutex::new(0));
+        let statuses = vec![StatusCode::SERVICE_UNAVAILABLE, StatusCode::SERVICE_UNAVAILABLE, StatusCode::BAD_REQUEST];
+        let address = flaky_peer(&pki, statuses, attempts.clone()).await;
+
+        let forwarder = Forwarder::new(pki.client("gateway-a"), false).with_retry(retry_policy(1));
+        let result = forwarder.forward(&address, &message(0)).await;
+        assert!(matches!(result, Err(ForwardError::Status(StatusCode::SERVICE_UNAVAILABLE))));
+        assert_eq!(*attempts.lock().unwrap(), 2);
+
+        // A 4xx is the peer's final answer
+        let forwarder = forwarder.with_retry(retry_policy(5));
+        let result = forwarder.forward(&address, &message(0)).await;
+        assert!(matches!(result, Err(ForwardError::Status(StatusCode::BAD_REQUEST))));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_peer_protocol_selects_client() {
+        async fn receive(version: axum::http::Version, Json(request): Json<ReceiveMessageRequest>) -> Json<SendMessageResponse> {
+            Json(SendMessageResponse {
+                status: format!("{:?}", version),
+                route: request.route,
+                message_id: None,
+                error: None,
+            })
+        }
+
+        let pki = TestPki::generate(&["gateway-a", "gateway-b", "gateway-c"]);
+        let app = Router::new().route("/message/receive", post(receive));
+        let h2_peer = pki.serve_h2("gateway-b", app.clone()).await.to_string();
+        let http1_peer = pki.serve_h2("gateway-c", app).await.to_string();
+
+        let peers = vec![PeerConfig {
+            node_id: "gateway-c".to_string(),
+            address: http1_peer.clone(),
+            protocol: PeerProtocol::Http1,
+            cost: 1,
+        }];
+        let clients = PeerClients::new(pki.cert_path("gateway-a"), pki.key_path("gateway-a"), &[pki.ca_path()], ClientTimeouts::default(), PeerNameVerification::Strict, &[]).unwrap();
+        let forwarder = Forwarder::new(pki.client("gateway-a"), false).with_peer_protocols(clients, &peers);
+
+        let response = forwarder.forward(&h2_peer, &message(0)).await.unwrap();
+        assert_eq!(response.status, "HTTP/2.0");
+
+        let response = forwarder.forward(&http1_peer, &message(0)).await.unwrap();
+        assert_eq!(response.status, "HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_priority_in_dscp_class_goes_over_marked_connection() {
+        async fn receive(version: axum::http::Version, Json(request): Json<ReceiveMessageRequest>) -> Json<SendMessageResponse> {
+            Json(SendMessageResponse {
+                status: format!("{:?}", version),
+                route: request.route,
+                message_id: None,
+                error: None,
+            })
+        }
+
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let address = pki.serve_h2("gateway-b", Router::new().route("/message/receive", post(receive))).await.to_string();
+
+        let classes = [crate::dscp::DscpClass { min_priority: 5, dscp: 46 }];
+        let clients = PeerClients::new(pki.cert_path("gateway-a"), pki.key_path("gateway-a"), &[pki.ca_path()], ClientTimeouts::default(), PeerNameVerification::Strict, &classes).unwrap();
+        let forwarder = Forwarder::new(pki.client("gateway-a"), false).with_peer_protocols(clients, &[]);
+
+        let response = forwarder.forward(&address, &message(0)).await.unwrap();
+        assert_eq!(response.status, "HTTP/2.0");
+        assert_eq!(forwarder.connection_stats().handshakes(), 1);
+
+        // The marked client negotiates h2 too, over a connection of its own
+        let urgent = ReceiveMessageRequest { priority: 7, ..message(1) };
+        let response = forwarder.forward(&address, &urgent).await.unwrap();
+        assert_eq!(response.status, "HTTP/2.0");
+        assert_eq!(forwarder.connection_stats().handshakes(), 2);
+
+        forwarder.forward(&address, &urgent).await.unwrap();
+        assert_eq!(forwarder.connection_stats().handshakes(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_opens_one_connection_per_peer() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b", "gateway-c"]);
+
+        async fn health(State(checks): State<Arc<Mutex<usize>>>) -> &'static str {
+            *checks.lock().unwrap() += 1;
+            "ok"
+        }
+        async fn receive(Json(request): Json<ReceiveMessageRequest>) -> Json<SendMessageResponse> {
+            Json(SendMessageResponse {
+                status: "delivered".to_string(),
+                route: request.route,
+                message_id: None,
+                error: None,
+            })
+        }
+        let mut peers = Vec::new();
+        for node in ["gateway-b", "gateway-c"] {
+            let checks = Arc::new(Mutex::new(0));
+            let app = Router::new()
+                .route("/health", axum::routing::get(health))
+                .route("/message/receive", post(receive))
+                .with_state(checks.clone());
+            peers.push((pki.serve(node, app).await.to_string(), checks));
+        }
+
+        let clients = PeerClients::new(pki.cert_path("gateway-a"), pki.key_path("gateway-a"), &[pki.ca_path()], ClientTimeouts::default(), PeerNameVerification::Strict, &[]).unwrap();
+        let forwarder = Forwarder::new(Client::new(), false).with_peer_protocols(clients, &[]);
+        let addresses: Vec<String> = peers.iter().map(|(address, _)| address.clone()).collect();
+
+        assert_eq!(forwarder.warm_up(&addresses).await, 2);
+        for (_, checks) in &peers {
+            assert_eq!(*checks.lock().unwrap(), 1);
+        }
+        let stats = forwarder.connection_stats();
+        assert_eq!((stats.requests(), stats.handshakes()), (2, 2));
+
+        // The first message then goes over the warmed connection
+        forwarder.forward(&addresses[0], &message(1)).await.unwrap();
+        assert_eq!((stats.requests(), stats.handshakes(), stats.reused()), (3, 2, 1));
+    }
+
+}
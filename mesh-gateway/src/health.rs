@@ -0,0 +1,141 @@
+use crate::types::PeerStatus;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of recent health checks a peer's score is computed over
+const HEALTH_WINDOW: usize = 10;
+
+/// Health checks answered within this time count as fully healthy; slower
+/// answers lower the score in proportion
+const TARGET_LATENCY: Duration = Duration::from_millis(250);
+
+/// Peers scoring at least this are Connected; below it they are Degraded
+pub const HEALTHY_SCORE: f64 = 0.8;
+
+/// Lowest score a Degraded peer's link cost is divided by, so a peer that is
+/// only just recovering costs at most five times its configured cost
+pub const MIN_LINK_SCORE: f64 = 0.2;
+
+/// Results of a peer's most recent health checks
+///
+/// Each entry is the check's round-trip time, or `None` if it failed.
+#[derive(Debug, Clone, Default)]
+pub struct HealthHistory {
+    checks: VecDeque<Option<Duration>>,
+}
+
+impl HealthHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a health check, forgetting the oldest beyond `HEALTH_WINDOW`
+    pub fn record(&mut self, result: Option<Duration>) {
+        if self.checks.len() == HEALTH_WINDOW {
+            self.checks.pop_front();
+        }
+        self.checks.push_back(result);
+    }
+
+    /// Score from 0.0 to 1.0: the fraction of checks that succeeded, scaled
+    /// down when successful checks averaged slower than `TARGET_LATENCY`
+    /// (None before the first check)
+    pub fn score(&self) -> Option<f64> {
+        if self.checks.is_empty() {
+            return None;
+        }
+
+        let latencies: Vec<Duration> = self.checks.iter().flatten().copied().collect();
+        if latencies.is_empty() {
+            return Some(0.0);
+        }
+
+        let success_rate = latencies.len() as f64 / self.checks.len() as f64;
+        let average = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+        let latency_factor = if average <= TARGET_LATENCY {
+            1.0
+        } else {
+            TARGET_LATENCY.as_secs_f64() / average.as_secs_f64()
+        };
+        Some(success_rate * latency_factor)
+    }
+
+    /// The peer status the history calls for
+    ///
+    /// A peer whose last check failed is Disconnected whatever its score,
+    /// as before scoring; one whose last check passed is Connected if it
+    /// scores well enough and Degraded otherwise, so a recovering peer is
+    /// back in use as soon as it answers.
+    pub fn status(&self) -> PeerStatus {
+        let (Some(last), Some(score)) = (self.checks.back(), self.score()) else {
+            return PeerStatus::Unknown;
+        };
+
+        if last.is_none() {
+            PeerStatus::Disconnected
+        } else if score < HEALTHY_SCORE {
+            PeerStatus::Degraded
+        } else {
+            PeerStatus::Connected
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: Option<Duration> = Some(Duration::from_millis(5));
+    const FAILED: Option<Duration> = None;
+
+    fn history(checks: &[Option<Duration>]) -> HealthHistory {
+        let mut history = HealthHistory::new();
+        for &check in checks {
+            history.record(check);
+        }
+        history
+    }
+
+    #[test]
+    fn test_score_over_check_sequences() {
+        assert_eq!(HealthHistory::new().score(), None);
+        assert_eq!(HealthHistory::new().status(), PeerStatus::Unknown);
+
+        let steady = history(&[FAST; 5]);
+        assert_eq!(steady.score(), Some(1.0));
+        assert_eq!(steady.status(), PeerStatus::Connected);
+
+        // Flapping: half the checks fail, and it's up right now
+        let flapping = history(&[FAST, FAILED, FAST, FAILED, FAST, FAILED, FAST, FAILED, FAST, FAILED, FAST]);
+        assert_eq!(flapping.score(), Some(0.5));
+        assert_eq!(flapping.status(), PeerStatus::Degraded);
+
+        // Slow: every check passes, at twice the target latency
+        let slow = history(&[Some(TARGET_LATENCY * 2); 4]);
+        assert_eq!(slow.score(), Some(0.5));
+        assert_eq!(slow.status(), PeerStatus::Degraded);
+
+        // A failed last check means down, however good the history
+        let just_failed = history(&[FAST, FAST, FAST, FAILED]);
+        assert_eq!(just_failed.score(), Some(0.75));
+        assert_eq!(just_failed.status(), PeerStatus::Disconnected);
+
+        // Answering again after a run of failures is at least Degraded
+        let mut recovering = history(&[FAILED; 9]);
+        recovering.record(FAST);
+        assert_eq!(recovering.score(), Some(0.1));
+        assert_eq!(recovering.status(), PeerStatus::Degraded);
+    }
+
+    #[test]
+    fn test_old_checks_age_out_of_window() {
+        let mut recovering = history(&[FAILED; HEALTH_WINDOW]);
+        assert_eq!(recovering.score(), Some(0.0));
+
+        for _ in 0..HEALTH_WINDOW {
+            recovering.record(FAST);
+        }
+        assert_eq!(recovering.score(), Some(1.0));
+        assert_eq!(recovering.status(), PeerStatus::Connected);
+    }
+}
@@ -4,9 +4,35 @@
 // 2. Organize code separately from the CLI entry point
 // 3. Reuse modules across multiple binaries (e.g., main.rs and gen_certs.rs)
 
+pub mod acks;
+pub mod audit;
+pub mod cert_gen;
+pub mod cert_watch;
 pub mod certs;
+pub mod circuit_breaker;
 pub mod client;
+pub mod compression;
 pub mod config;
+pub mod convergence;
+pub mod dedup;
+pub mod dscp;
+pub mod encryption;
+pub mod flood_throttle;
+pub mod forward_queue;
+pub mod forwarding;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod preflight;
+pub mod rate_limit;
+pub mod receipts;
+pub mod route_report;
 pub mod routing;
+pub mod runtime;
 pub mod server;
+pub mod signing;
 pub mod types;
+pub mod wire;
+
+#[cfg(test)]
+mod test_util;
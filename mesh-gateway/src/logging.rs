@@ -0,0 +1,159 @@
+use crate::config::{load_config_table, LogFormat};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable overriding the configured `log_format` ("json" or "text")
+pub const LOG_FORMAT_ENV: &str = "MESH_LOG_FORMAT";
+
+/// The settings needed to set up logging, read ahead of the full config so
+/// that warnings from loading it are already logged in the chosen format
+#[derive(Debug, Default, Deserialize)]
+pub struct LogSettings {
+    #[serde(default)]
+    pub node_id: Option<String>,
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+impl LogSettings {
+    /// Read the logging settings from a config file, falling back to the
+    /// defaults if it can't be read (loading the full config reports why)
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        load_config_table(path.as_ref())
+            .ok()
+            .and_then(|(table, _)| toml::Value::Table(table).try_into().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// The log format to use: `env` (the value of `MESH_LOG_FORMAT`, if set)
+/// wins over `configured`; an unrecognized value is ignored
+pub fn resolve_log_format(configured: LogFormat, env: Option<&str>) -> LogFormat {
+    match env.map(|value| value.trim().to_ascii_lowercase()).as_deref() {
+        Some("json") => LogFormat::Json,
+        Some("text") => LogFormat::Text,
+        _ => configured,
+    }
+}
+
+/// Build the subscriber for `format`, writing to `writer`
+///
+/// In JSON mode every line carries a top-level `node_id` field (when known),
+/// including lines logged from background tasks, so the logs of several
+/// nodes can be shipped to one place and filtered apart.
+pub fn subscriber<W>(
+    format: LogFormat,
+    node_id: Option<&str>,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+
+    match format {
+        LogFormat::Text => Box::new(builder.finish()),
+        LogFormat::Json => {
+            let format = WithNodeId {
+                inner: tracing_subscriber::fmt::format().json().flatten_event(true),
+                node_id: node_id.map(|id| serde_json::Value::from(id).to_string()),
+            };
+            Box::new(builder.json().event_format(format).finish())
+        }
+    }
+}
+
+/// Install the global subscriber, logging to stdout at `RUST_LOG` (default "info")
+pub fn init(format: LogFormat, node_id: Option<&str>) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing::subscriber::set_global_default(subscriber(format, node_id, filter, std::io::stdout))
+        .expect("tracing subscriber already installed");
+}
+
+/// JSON event format that adds a `node_id` field to each line
+struct WithNodeId<F> {
+    inner: F,
+    /// node_id, already encoded as a JSON string
+    node_id: Option<String>,
+}
+
+impl<S, N, F> FormatEvent<S, N> for WithNodeId<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let Some(node_id) = &self.node_id else {
+            return self.inner.format_event(ctx, writer, event);
+        };
+
+        let mut line = String::new();
+        self.inner.format_event(ctx, Writer::new(&mut line), event)?;
+        match line.strip_prefix('{') {
+            Some(rest) => write!(writer, "{{\"node_id\":{},{}", node_id, rest),
+            None => writer.write_str(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects everything logged, for inspection
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = subscriber(format, Some("gateway-a"), EnvFilter::new("info"), move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || tracing::info!(peer = "gateway-b", "Peer connected"));
+
+        String::from_utf8(captured.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_env_overrides_configured_format() {
+        assert_eq!(resolve_log_format(LogFormat::Text, None), LogFormat::Text);
+        assert_eq!(resolve_log_format(LogFormat::Json, None), LogFormat::Json);
+        assert_eq!(resolve_log_format(LogFormat::Text, Some("JSON")), LogFormat::Json);
+        assert_eq!(resolve_log_format(LogFormat::Json, Some("text")), LogFormat::Text);
+        assert_eq!(resolve_log_format(LogFormat::Json, Some("yaml")), LogFormat::Json);
+    }
+
+    #[test]
+    fn test_json_lines_carry_node_id() {
+        let line = log_line(LogFormat::Json);
+        let record: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(record["node_id"], "gateway-a");
+        assert_eq!(record["message"], "Peer connected");
+        assert_eq!(record["peer"], "gateway-b");
+        assert_eq!(record["level"], "INFO");
+
+        let text = log_line(LogFormat::Text);
+        assert!(text.contains("Peer connected"));
+        assert!(serde_json::from_str::<serde_json::Value>(text.trim()).is_err());
+    }
+}
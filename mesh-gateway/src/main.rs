@@ -1,84 +1,309 @@
-use anyhow::Result;
-use clap::Parser;
-use mesh_gateway::client::create_mtls_client;
-use mesh_gateway::config::GatewayConfig;
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use mesh_gateway::cert_gen::{generate_certificates, GenCertsArgs};
+use mesh_gateway::certs::{check_cert_validity, load_cert};
+use mesh_gateway::client::{self, create_mtls_client};
+use mesh_gateway::config::{GatewayConfig, StartupDiagnostics};
+use mesh_gateway::logging::{self, resolve_log_format, LogSettings, LOG_FORMAT_ENV};
+use mesh_gateway::preflight::check_deployment;
+use mesh_gateway::route_report::{describe_route, format_route};
 use mesh_gateway::routing::RoutingTable;
-use std::net::SocketAddr;
+use mesh_gateway::runtime::GatewayRuntime;
+use mesh_gateway::signing::LsaSigner;
+use mesh_gateway::server::ReloadSignal;
+use mesh_gateway::types::TopologySnapshot;
+use std::collections::HashMap;
 
 #[derive(Parser, Debug)]
 #[command(name = "mesh-gateway")]
 #[command(about = "Zero-trust mesh gateway network", long_about = None)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Without a subcommand, the gateway is started with these arguments
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start the gateway (the default when no subcommand is given)
+    Run(RunArgs),
+    /// Print the computed shortest path to a destination node
+    Routes(RoutesArgs),
+    /// Generate the Root CA and gateway certificates
+    GenCerts(GenCertsArgs),
+    /// Check a config file and its certificates without starting the gateway
+    Validate(ValidateArgs),
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
     /// Path to configuration file
     #[arg(short, long, default_value = "configs/gateway-a.toml")]
     config: String,
+
+    /// Also print startup diagnostics as a single JSON line on stdout
+    #[arg(long)]
+    startup_json: bool,
+}
+
+#[derive(Args, Debug)]
+struct RoutesArgs {
+    /// Path to configuration file of the node to route from
+    #[arg(short, long, default_value = "configs/gateway-a.toml")]
+    config: String,
+
+    /// Destination node id
+    #[arg(long)]
+    to: String,
+
+    /// Fetch the link-state database from the running node at this address
+    /// (host:port) over mTLS; otherwise only the configured peers are known
+    #[arg(long, value_name = "HOST:PORT")]
+    query: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct ValidateArgs {
+    /// Path to configuration file to check
+    #[arg(short, long, default_value = "configs/gateway-a.toml")]
+    config: String,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Run(cli.run));
+
+    // Initialize tracing in the configured (or MESH_LOG_FORMAT) format
+    let settings = match &command {
+        Command::Run(args) => LogSettings::from_file(&args.config),
+        _ => LogSettings::default(),
+    };
+    let env_format = std::env::var(LOG_FORMAT_ENV).ok();
+    logging::init(
+        resolve_log_format(settings.log_format, env_format.as_deref()),
+        settings.node_id.as_deref(),
+    );
 
-    let args = Args::parse();
+    match command {
+        Command::Run(args) => run(args).await,
+        Command::Routes(args) => routes(args).await,
+        Command::GenCerts(args) => generate_certificates(&args.nodes, &args.sans, args.auto_san, args.new_ca).map_err(|e| anyhow::anyhow!("{}", e)),
+        Command::Validate(args) => validate(args),
+    }
+}
 
+async fn run(args: RunArgs) -> Result<()> {
     // Load configuration from file
     tracing::info!("📄 Loading configuration from: {}", args.config);
     let config = GatewayConfig::from_file(&args.config)?;
 
+    // Fail fast on an expired certificate instead of failing every handshake
+    let server_cert = load_cert(&config.cert_path)?;
+    check_cert_validity(&server_cert[0])?;
+
     tracing::info!("🚀 Starting Mesh Gateway: {}", config.node_id);
     tracing::info!("📁 Certificate: {}", config.cert_path);
     tracing::info!("🔐 Private Key: {}", config.key_path);
     tracing::info!("🏛️  CA Certificate: {}", config.ca_cert_path);
     tracing::info!("👥 Configured peers: {}", config.peers.len());
+    if config.ordered_forwarding {
+        tracing::info!("📬 Ordered forwarding enabled (one send queue per next hop)");
+    }
+
+    // Structured startup record for log aggregation
+    let diagnostics = StartupDiagnostics::from_config(&config);
+    diagnostics.emit();
+    if args.startup_json {
+        println!("{}", serde_json::to_string(&diagnostics)?);
+    }
 
     // Create routing table from config
-    let routing_table = RoutingTable::from_config(config.peers.clone());
+    let routing_table = RoutingTable::from_config(&config.node_id, config.peers.clone());
     tracing::info!("🗺️  Routing table initialized with {} peers", routing_table.peer_count());
 
+    if let Some(scope) = config.lsa_flood_scope {
+        routing_table.set_lsa_flood_scope(Some(scope));
+        tracing::info!("📡 LSA flooding limited to {} hop(s)", scope);
+    }
+
+    if let Some(max_hops) = config.max_route_hops {
+        routing_table.set_max_route_hops(Some(max_hops));
+        tracing::info!("🧭 Routes limited to {} hop(s)", max_hops);
+    }
+
+    if config.stability_tiebreak {
+        routing_table.set_stability_tiebreak(true);
+        tracing::info!("⚖️  Preferring stable paths among equal-hop routes");
+    }
+
+    if config.sign_lsas {
+        let signer = LsaSigner::from_files(&config.cert_path, &config.key_path)?;
+        routing_table.set_lsa_signer(signer);
+        tracing::info!("✍️  LSA signing enabled");
+    }
+
     // Create mTLS HTTP client for communicating with peers
     tracing::info!("🔐 Creating mTLS HTTP client...");
     let http_client = create_mtls_client(
         &config.cert_path,
         &config.key_path,
-        &config.ca_cert_path,
+        &config.trusted_ca_paths(),
+        config.client_timeouts(),
+        config.peer_name_verification,
     )?;
     tracing::info!("✓ mTLS client ready");
 
-    let listen_addr: SocketAddr = config.listen_addr().parse()?;
+    let listen_addrs = config.listen_addrs()?;
 
-    // Spawn background task for peer health checks
-    tracing::info!("🏥 Starting peer health monitoring...");
-    mesh_gateway::server::spawn_health_check_task(
-        routing_table.clone(),
-        http_client.clone(),
-    );
-    tracing::info!("✓ Health check task started (15s interval)");
+    // Build the shared state and start background tasks (health checks,
+    // LSA broadcasts, optional peer pruning) owned by the runtime
+    let state = mesh_gateway::server::build_app_state(config, routing_table, http_client)?;
+    let config = state.config();
 
-    // Spawn background task for LSA broadcasts
+    tracing::info!("🏥 Starting peer health monitoring...");
     tracing::info!("🔄 Starting link-state routing protocol...");
-    mesh_gateway::server::spawn_lsa_broadcast_task(
-        config.node_id.clone(),
-        routing_table.clone(),
-        http_client.clone(),
-    );
-    tracing::info!("✓ LSA broadcast task started (30s interval)");
-
-    // Start the HTTPS server
-    mesh_gateway::server::start_server(
-        config.node_id,
-        listen_addr,
-        config.cert_path,
-        config.key_path,
-        config.ca_cert_path,
-        routing_table,
-        http_client,
-    )
-    .await?;
+    let tls_config = mesh_gateway::server::load_server_tls(&config)?;
+    let mut runtime = GatewayRuntime::start(state.clone()).with_tls(tls_config.clone());
+    tracing::info!("✓ Health check task started ({}s interval)", config.health_check_interval_secs);
+    tracing::info!("✓ LSA broadcast task started ({}s interval)", config.lsa_interval_secs);
+    tracing::info!("✓ LSA aging task started ({}s max age)", config.lsa_max_age_secs);
+    if let Some(max_down_secs) = config.peer_max_down_secs {
+        tracing::info!("✓ Peer pruning enabled (max down time {}s)", max_down_secs);
+    }
+    if let Some(stale_secs) = config.peer_stale_secs {
+        tracing::info!("✓ Peer staleness check enabled ({}s without contact)", stale_secs);
+    }
+    if let Some(period_secs) = config.reachability_log_secs {
+        tracing::info!("✓ Reachability logging enabled ({}s interval, debug level)", period_secs);
+    }
+    if let Some(command) = &config.peer_status_command {
+        tracing::info!("✓ Running `{}` on peer status changes", command);
+    }
+    if let Some(period_secs) = config.cert_watch_secs {
+        tracing::info!("✓ Watching certificate files for changes ({}s interval)", period_secs);
+    }
+    if config.warm_connections {
+        // In the background, so an unreachable peer doesn't hold up startup
+        let forwarder = state.forwarder.clone();
+        let addresses: Vec<String> = config.peers.iter().map(|peer| peer.address.clone()).collect();
+        tokio::spawn(async move {
+            let warmed = forwarder.warm_up(&addresses).await;
+            tracing::info!("✓ Warmed connections to {}/{} peer(s)", warmed, addresses.len());
+        });
+    }
+
+    // Start the HTTPS server; it returns once a shutdown signal has drained
+    // it. Meanwhile SIGHUP re-reads the config file and applies it in place.
+    let server = mesh_gateway::server::start_server(state, listen_addrs, tls_config, mesh_gateway::server::shutdown_signal());
+    tokio::pin!(server);
+    let mut reload_signal = ReloadSignal::new();
+    loop {
+        tokio::select! {
+            result = &mut server => {
+                result?;
+                break;
+            }
+            _ = reload_signal.recv() => {
+                tracing::info!("🔁 SIGHUP received, reloading configuration from {}", args.config);
+                if let Err(e) = runtime.reload_from_file(&args.config) {
+                    tracing::error!("Configuration reload failed, keeping current configuration: {:#}", e);
+                }
+            }
+        }
+    }
+
+    runtime.shutdown().await;
+    tracing::info!("👋 Gateway stopped");
+
+    Ok(())
+}
+
+/// Check the config file and the files it references, printing every
+/// problem found; fails if there were any
+fn validate(args: ValidateArgs) -> Result<()> {
+    let problems = check_deployment(&args.config);
+    if problems.is_empty() {
+        println!("✓ {} is valid", args.config);
+        return Ok(());
+    }
+
+    eprintln!("{} has {} problem(s):", args.config, problems.len());
+    for problem in &problems {
+        eprintln!("  - {}", problem);
+    }
+    anyhow::bail!("validation failed")
+}
+
+/// Compute and print the route from the configured node to `args.to`,
+/// failing if there is none
+async fn routes(args: RoutesArgs) -> Result<()> {
+    let config = GatewayConfig::from_file(&args.config)?;
+
+    let routing_table = RoutingTable::from_config(&config.node_id, config.peers.clone());
+    routing_table.set_stability_tiebreak(config.stability_tiebreak);
+    routing_table.set_max_route_hops(config.max_route_hops);
+
+    let mut addresses = HashMap::new();
+    let path = match &args.query {
+        Some(address) => {
+            let http_client = create_mtls_client(
+                &config.cert_path,
+                &config.key_path,
+                &config.trusted_ca_paths(),
+                config.client_timeouts(),
+                config.peer_name_verification,
+            )?;
+            let snapshot = fetch_topology(&http_client, address)
+                .await
+                .with_context(|| format!("failed to query topology from {}", address))?;
+            routing_table.load_topology_snapshot(snapshot);
+
+            let path = routing_table.find_route_from(&config.node_id, &args.to);
+
+            // Beyond the first hop, addresses come from each hop's own peer table
+            for pair in path.iter().flat_map(|path| path.windows(2)) {
+                let Some(hop_address) = addresses
+                    .get(&pair[0])
+                    .cloned()
+                    .or_else(|| routing_table.get_peer(&pair[0]).map(|peer| peer.address))
+                else {
+                    break;
+                };
+                match fetch_topology(&http_client, &hop_address).await {
+                    Ok(snapshot) => {
+                        if let Some(next) = snapshot.peers.into_iter().find(|peer| peer.node_id == pair[1]) {
+                            addresses.insert(next.node_id, next.address);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not query {} at {}: {}", pair[0], hop_address, e),
+                }
+            }
+
+            path
+        }
+        None => {
+            // Without a live view, assume every configured link is up
+            routing_table.mark_all_connected();
+            routing_table.find_route_from(&config.node_id, &args.to)
+        }
+    };
+
+    let Some(path) = path else {
+        anyhow::bail!("no route from {} to {}", config.node_id, args.to);
+    };
+
+    let hops = describe_route(&routing_table, &config.node_id, &path, &addresses);
+    print!("{}", format_route(&config.node_id, &args.to, &hops));
 
     Ok(())
 }
+
+async fn fetch_topology(http_client: &reqwest::Client, address: &str) -> Result<TopologySnapshot> {
+    let body = client::get(http_client, &format!("https://{}/topology", address)).await?;
+    Ok(serde_json::from_str(&body)?)
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters for messages handled by this node, exported at /metrics
+#[derive(Clone, Default)]
+pub struct MessageCounters {
+    /// Messages originated here via /message/send
+    pub sent: Arc<AtomicU64>,
+    /// Messages that arrived from peers via /message/receive
+    pub received: Arc<AtomicU64>,
+    /// Messages handed off to a next hop
+    pub forwarded: Arc<AtomicU64>,
+    /// Messages this node gave up on (no route, loop, TTL, failed forward)
+    pub dropped: Arc<AtomicU64>,
+    /// Repeat deliveries of a message id already handled here
+    pub duplicates: Arc<AtomicU64>,
+}
+
+impl MessageCounters {
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(counter: &AtomicU64) -> u64 {
+        counter.load(Ordering::Relaxed)
+    }
+}
+
+/// Builds a response body in the Prometheus text exposition format
+#[derive(Default)]
+pub struct PrometheusText {
+    body: String,
+}
+
+impl PrometheusText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a metric with a single unlabeled sample
+    pub fn metric(&mut self, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+        self.header(name, kind, help);
+        let _ = writeln!(self.body, "{} {}", name, value);
+    }
+
+    /// Add a metric with one sample per value of `label`
+    pub fn labeled_metric<V: std::fmt::Display>(
+        &mut self,
+        name: &str,
+        kind: &str,
+        help: &str,
+        label: &str,
+        samples: &[(&str, V)],
+    ) {
+        self.header(name, kind, help);
+        for (label_value, value) in samples {
+            let _ = writeln!(self.body, "{}{{{}=\"{}\"}} {}", name, label, label_value, value);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.body
+    }
+
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.body, "# HELP {} {}", name, help);
+        let _ = writeln!(self.body, "# TYPE {} {}", name, kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_text_format() {
+        let mut text = PrometheusText::new();
+        text.metric("mesh_messages_sent_total", "counter", "Messages sent", 3);
+        text.labeled_metric("mesh_peers", "gauge", "Peers by status", "status", &[("connected", 2)]);
+
+        assert_eq!(
+            text.finish(),
+            "# HELP mesh_messages_sent_total Messages sent\n\
+             # TYPE mesh_messages_sent_total counter\n\
+             mesh_messages_sent_total 3\n\
+             # HELP mesh_peers Peers by status\n\
+             # TYPE mesh_peers gauge\n\
+             mesh_peers{status=\"connected\"} 2\n"
+        );
+    }
+}
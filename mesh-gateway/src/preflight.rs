@@ -0,0 +1,107 @@
+use crate::certs::{check_cert_validity, load_ca_cert, load_ca_certs, load_cert, load_private_key, verify_cert_chain, verify_key_matches_cert};
+use crate::config::GatewayConfig;
+use std::path::Path;
+
+/// Check that a gateway could start with the config file at `path`, without
+/// starting it, as done by `mesh-gateway validate`
+///
+/// Returns every problem found; an empty list means the config parses, its
+/// peer addresses are well-formed, and the certificate, key and CA files load
+/// and fit together. Certificate checks are skipped if the config itself
+/// can't be loaded.
+pub fn check_deployment(path: impl AsRef<Path>) -> Vec<String> {
+    let config = match GatewayConfig::from_file(path) {
+        Ok(config) => config,
+        Err(e) => return vec![format!("{:#}", e)],
+    };
+
+    let mut problems = Vec::new();
+    let mut record = |result: anyhow::Result<()>| {
+        if let Err(e) = result {
+            problems.push(format!("{:#}", e));
+        }
+    };
+
+    let cert = load_cert(&config.cert_path);
+    let key = load_private_key(&config.key_path);
+    let ca_paths = config.trusted_ca_paths();
+    let ca_files_ok = ca_paths.iter().fold(true, |ok, ca_path| {
+        let loaded = load_ca_cert(ca_path).map(|_| ());
+        let loaded_ok = loaded.is_ok();
+        record(loaded);
+        ok && loaded_ok
+    });
+
+    if let Ok(chain) = &cert {
+        if let Some(end_entity) = chain.first() {
+            record(check_cert_validity(end_entity));
+        }
+        if ca_files_ok {
+            let verified = load_ca_certs(&ca_paths).and_then(|roots| verify_cert_chain(chain, roots));
+            record(verified.map_err(|e| e.context(format!("{} does not chain to the CA", config.cert_path))));
+        }
+        if let (Some(end_entity), Ok(key)) = (chain.first(), &key) {
+            record(verify_key_matches_cert(end_entity, key).map_err(|e| {
+                e.context(format!("{} does not belong to {}", config.key_path, config.cert_path))
+            }));
+        }
+    }
+    record(cert.map(|_| ()));
+    record(key.map(|_| ()));
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestPki;
+    use std::fs;
+
+    fn write_config(pki: &TestPki, name: &str, cert_node: &str, key_node: &str, peer_address: &str) -> std::path::PathBuf {
+        let path = pki.ca_path().with_file_name(format!("{}.toml", name));
+        fs::write(
+            &path,
+            format!(
+                "node_id = \"gateway-a\"\n\
+                 listen_port = 8001\n\
+                 cert_path = {:?}\n\
+                 key_path = {:?}\n\
+                 ca_cert_path = {:?}\n\
+                 [[peers]]\n\
+                 node_id = \"gateway-b\"\n\
+                 address = {:?}\n",
+                pki.cert_path(cert_node),
+                pki.key_path(key_node),
+                pki.ca_path(),
+                peer_address
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_valid_bundle_has_no_problems() {
+        let pki = TestPki::generate(&["gateway-a"]);
+        let path = write_config(&pki, "valid", "gateway-a", "gateway-a", "127.0.0.1:8002");
+
+        assert_eq!(check_deployment(&path), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_mismatched_key_and_bad_address_are_reported() {
+        let pki = TestPki::generate(&["gateway-a", "gateway-b"]);
+        let path = write_config(&pki, "mismatched", "gateway-a", "gateway-b", "127.0.0.1:8002");
+
+        let problems = check_deployment(&path);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("does not belong to"), "{}", problems[0]);
+
+        // A malformed peer address fails the config itself
+        let path = write_config(&pki, "bad-address", "gateway-a", "gateway-a", "https://127.0.0.1:8002");
+        let problems = check_deployment(&path);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("Invalid peer address"), "{}", problems[0]);
+    }
+}
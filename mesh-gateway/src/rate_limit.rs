@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Per-peer token buckets for one class of inbound requests
+///
+/// Each peer may make `burst` requests at once, after which its bucket
+/// refills at `rate_per_sec`. The rate and burst are passed on every call so
+/// a configuration reload takes effect without resetting the buckets.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token from `peer`'s bucket, returning false if it is empty
+    pub fn allow(&self, peer: &str, rate_per_sec: f64, burst: u32) -> bool {
+        self.allow_at(peer, rate_per_sec, burst, Instant::now())
+    }
+
+    fn allow_at(&self, peer: &str, rate_per_sec: f64, burst: u32, now: Instant) -> bool {
+        let capacity = f64::from(burst.max(1));
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(peer.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate_per_sec).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_burst_beyond_limit_is_rejected() {
+        let limiter = RateLimiter::new();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.allow_at("gateway-b", 1.0, 3, now));
+        }
+        assert!(!limiter.allow_at("gateway-b", 1.0, 3, now));
+
+        // Other peers have their own bucket
+        assert!(limiter.allow_at("gateway-c", 1.0, 3, now));
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = RateLimiter::new();
+        let start = Instant::now();
+
+        assert!(limiter.allow_at("gateway-b", 2.0, 1, start));
+        assert!(!limiter.allow_at("gateway-b", 2.0, 1, start + Duration::from_millis(100)));
+        assert!(limiter.allow_at("gateway-b", 2.0, 1, start + Duration::from_millis(600)));
+
+        // A long pause refills only up to the burst size
+        let later = start + Duration::from_secs(60);
+        assert!(limiter.allow_at("gateway-b", 2.0, 1, later));
+        assert!(!limiter.allow_at("gateway-b", 2.0, 1, later));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::types::MessageReceipt;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bounded store of final statuses for messages this node originated
+///
+/// Holds at most `capacity` receipts, evicting the least recently used one
+/// when full. Receipts also expire after `ttl`; expired entries are dropped
+/// when looked up and by `sweep_expired`, which a background task calls so
+/// memory is reclaimed even if nobody queries.
+#[derive(Clone)]
+pub struct ReceiptStore {
+    inner: Arc<Mutex<StoreInner>>,
+    ttl: Duration,
+}
+
+struct StoreInner {
+    receipts: LruCache<String, (MessageReceipt, Instant)>,
+    /// Receipts dropped to make room for newer ones (expiry not included)
+    evictions: u64,
+}
+
+impl ReceiptStore {
+    /// Create a store holding up to `capacity` receipts (at least one) for `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            inner: Arc::new(Mutex::new(StoreInner {
+                receipts: LruCache::new(capacity),
+                evictions: 0,
+            })),
+            ttl,
+        }
+    }
+
+    /// Store (or replace) the receipt for a message
+    pub fn record(&self, receipt: MessageReceipt) {
+        self.record_at(receipt, Instant::now());
+    }
+
+    /// Look up a message's receipt, if it is still retained
+    pub fn get(&self, message_id: &str) -> Option<MessageReceipt> {
+        let mut inner = self.inner.lock().unwrap();
+        let (receipt, recorded_at) = inner.receipts.get(message_id)?;
+
+        if recorded_at.elapsed() >= self.ttl {
+            inner.receipts.pop(message_id);
+            return None;
+        }
+
+        Some(receipt.clone())
+    }
+
+    /// Remove every expired receipt, returning how many were removed
+    pub fn sweep_expired(&self) -> usize {
+        self.sweep_expired_at(Instant::now())
+    }
+
+    /// Number of receipts currently held
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().receipts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total receipts evicted because the store was full
+    pub fn evictions(&self) -> u64 {
+        self.inner.lock().unwrap().evictions
+    }
+
+    fn record_at(&self, receipt: MessageReceipt, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let message_id = receipt.message_id.clone();
+
+        // `push` also hands back the old value when replacing the same key
+        if let Some((evicted_id, _)) = inner.receipts.push(message_id.clone(), (receipt, now))
+            && evicted_id != message_id
+        {
+            inner.evictions += 1;
+        }
+    }
+
+    fn sweep_expired_at(&self, now: Instant) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+
+        let expired: Vec<String> = inner
+            .receipts
+            .iter()
+            .filter(|(_, (_, recorded_at))| now.saturating_duration_since(*recorded_at) >= self.ttl)
+            .map(|(message_id, _)| message_id.clone())
+            .collect();
+
+        for message_id in &expired {
+            inner.receipts.pop(message_id);
+        }
+
+        expired.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn receipt(message_id: &str) -> MessageReceipt {
+        MessageReceipt {
+            message_id: message_id.to_string(),
+            to: "gateway-c".to_string(),
+            status: "delivered".to_string(),
+            route: vec!["gateway-a".to_string(), "gateway-c".to_string()],
+            timestamp: Some(SystemTime::now()),
+        }
+    }
+
+    #[test]
+    fn test_exceeding_capacity_evicts_oldest() {
+        let store = ReceiptStore::new(3, Duration::from_secs(60));
+        for id in ["m1", "m2", "m3", "m4", "m5"] {
+            store.record(receipt(id));
+        }
+
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.evictions(), 2);
+        assert!(store.get("m1").is_none());
+        assert!(store.get("m2").is_none());
+        assert!(store.get("m5").is_some());
+
+        // Replacing an existing receipt is not an eviction
+        store.record(receipt("m5"));
+        assert_eq!(store.evictions(), 2);
+    }
+
+    #[test]
+    fn test_sweep_removes_expired_receipts() {
+        let store = ReceiptStore::new(10, Duration::from_secs(60));
+        let start = Instant::now();
+        store.record_at(receipt("old"), start);
+        store.record_at(receipt("new"), start + Duration::from_secs(50));
+
+        assert_eq!(store.sweep_expired_at(start + Duration::from_secs(70)), 1);
+        assert_eq!(store.len(), 1);
+        assert!(store.get("new").is_some());
+        assert_eq!(store.evictions(), 0);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::routing::RoutingTable;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// One hop of a computed route, as printed by `mesh-gateway routes`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteHop {
+    pub node_id: String,
+    /// Address of the hop, if known (direct peers, or learned from a live query)
+    pub address: Option<String>,
+    /// Cost of the link into this hop
+    pub cost: u32,
+}
+
+/// Describe `path` (as returned by `find_route_from`, excluding `source`)
+/// hop by hop, with the cost of each link and any known address
+pub fn describe_route(
+    table: &RoutingTable,
+    source: &str,
+    path: &[String],
+    addresses: &HashMap<String, String>,
+) -> Vec<RouteHop> {
+    let mut previous = source;
+    path.iter()
+        .map(|node_id| {
+            let hop = RouteHop {
+                node_id: node_id.clone(),
+                address: addresses
+                    .get(node_id)
+                    .cloned()
+                    .or_else(|| table.get_peer(node_id).map(|peer| peer.address)),
+                cost: table.link_cost(source, previous, node_id).unwrap_or(1),
+            };
+            previous = node_id;
+            hop
+        })
+        .collect()
+}
+
+/// Render a route as a header line followed by one numbered line per hop
+pub fn format_route(source: &str, destination: &str, hops: &[RouteHop]) -> String {
+    let total: u32 = hops.iter().map(|hop| hop.cost).sum();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} -> {}: {} hop(s), total cost {}",
+        source,
+        destination,
+        hops.len(),
+        total
+    );
+
+    let width = hops.iter().map(|hop| hop.node_id.len()).max().unwrap_or(0);
+    for (index, hop) in hops.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  {}. {:<width$}  {}  (cost {})",
+            index + 1,
+            hop.node_id,
+            hop.address.as_deref().unwrap_or("<unknown address>"),
+            hop.cost,
+            width = width
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PeerConfig, PeerProtocol};
+    use crate::types::LinkStateAdvertisement;
+    use std::collections::BTreeMap;
+    use std::time::SystemTime;
+
+    fn lsa(node_id: &str, neighbors: &[&str], costs: &[(&str, u32)]) -> LinkStateAdvertisement {
+        LinkStateAdvertisement {
+            node_id: node_id.to_string(),
+            neighbors: neighbors.iter().map(|n| n.to_string()).collect(),
+            neighbor_costs: costs.iter().map(|(n, c)| (n.to_string(), *c)).collect::<BTreeMap<_, _>>(),
+            sequence: 1,
+            epoch: 1,
+            timestamp: Some(SystemTime::now()),
+            flood_ttl: None,
+            signature: None,
+            signer_cert: None,
+            stability_hint: None,
+        }
+    }
+
+    #[test]
+    fn test_route_hops_carry_addresses_and_costs() {
+        let table = RoutingTable::from_config("gateway-a", vec![PeerConfig {
+            node_id: "gateway-b".to_string(),
+            address: "127.0.0.1:8002".to_string(),
+            protocol: PeerProtocol::default(),
+            cost: 2,
+        }]);
+        table.mark_all_connected();
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], &[("gateway-c", 3)]));
+        table.process_lsa(lsa("gateway-c", &["gateway-b"], &[]));
+
+        let path = table.find_route_from("gateway-a", "gateway-c").unwrap();
+        let addresses = HashMap::from([("gateway-c".to_string(), "10.0.0.3:8003".to_string())]);
+        let hops = describe_route(&table, "gateway-a", &path, &addresses);
+
+        assert_eq!(
+            hops,
+            vec![
+                RouteHop {
+                    node_id: "gateway-b".to_string(),
+                    address: Some("127.0.0.1:8002".to_string()),
+                    cost: 2,
+                },
+                RouteHop {
+                    node_id: "gateway-c".to_string(),
+                    address: Some("10.0.0.3:8003".to_string()),
+                    cost: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_route() {
+        let hops = vec![
+            RouteHop {
+                node_id: "gateway-b".to_string(),
+                address: Some("127.0.0.1:8002".to_string()),
+                cost: 1,
+            },
+            RouteHop {
+                node_id: "gw-c".to_string(),
+                address: None,
+                cost: 4,
+            },
+        ];
+
+        assert_eq!(
+            format_route("gateway-a", "gw-c", &hops),
+            "gateway-a -> gw-c: 2 hop(s), total cost 5\n\
+             \x20 1. gateway-b  127.0.0.1:8002  (cost 1)\n\
+             \x20 2. gw-c       <unknown address>  (cost 4)\n"
+        );
+    }
+}
@@ -1,14 +1,49 @@
 use crate::config::PeerConfig;
-use crate::types::{LinkStateAdvertisement, PeerInfo, PeerStatus};
-use std::collections::{HashMap, HashSet, BinaryHeap};
+use crate::convergence::RouteSnapshot;
+use crate::health::{HealthHistory, MIN_LINK_SCORE};
+use crate::signing::{LsaSigner, LsaVerifier};
+use crate::types::{LinkStateAdvertisement, PathAsymmetry, PeerInfo, PeerStatus, RoutingStats, TopologyNode, TopologySnapshot};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, BinaryHeap};
 use std::cmp::Ordering;
-use std::sync::{Arc, RwLock};
-use std::time::SystemTime;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// How many topology events a slow subscriber can fall behind before it lags
+const TOPOLOGY_EVENT_CAPACITY: usize = 256;
+
+/// A change to this node's view of the topology, published to subscribers
+/// (and streamed at `/topology/events`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TopologyEvent {
+    /// A new or newer LSA was stored in the database
+    LsaAccepted {
+        node_id: String,
+        sequence: u64,
+        /// False when the LSA only refreshed an identical neighbor list
+        neighbors_changed: bool,
+    },
+    /// A direct peer's status changed
+    PeerStatusChanged {
+        node_id: String,
+        previous: PeerStatus,
+        status: PeerStatus,
+    },
+    /// An LSA aged out of the database without being refreshed
+    LsaExpired { node_id: String },
+}
 
 /// Thread-safe routing table for tracking peers in the mesh network
 #[derive(Clone)]
 pub struct RoutingTable {
     inner: Arc<RwLock<RoutingTableInner>>,
+    events: broadcast::Sender<TopologyEvent>,
+    /// Randomness for route selection, shared with the `LoadBalancer`
+    rng: RouteRng,
 }
 
 struct RoutingTableInner {
@@ -21,6 +56,157 @@ struct RoutingTableInner {
 
     /// Sequence number for our own LSAs
     own_lsa_sequence: u64,
+
+    /// Boot epoch for this node, carried in our LSAs and health responses
+    epoch: u64,
+
+    /// Last boot epoch observed from each direct peer's health response
+    peer_epochs: HashMap<String, u64>,
+
+    /// Recent health-check results of each direct peer, behind its health score
+    peer_health: HashMap<String, HealthHistory>,
+
+    /// Peers that came from the configuration file (as opposed to being added at runtime)
+    static_peers: HashSet<String>,
+
+    /// When each peer that isn't currently Connected was last seen going down
+    /// (or was added, if it has never been up)
+    peer_down_since: HashMap<String, SystemTime>,
+
+    /// Signs our own LSAs when LSA signing is enabled
+    lsa_signer: Option<Arc<LsaSigner>>,
+
+    /// Checks the signature of every signed LSA before it is accepted
+    lsa_verifier: Option<LsaVerifier>,
+
+    /// Reject LSAs that carry no signature
+    require_signed_lsas: bool,
+
+    /// How many hops our own LSAs may be flooded (None = whole mesh)
+    lsa_flood_scope: Option<u8>,
+
+    /// Last time a peer came up or went down, or an LSA changed a node's neighbors
+    topology_changed_at: SystemTime,
+
+    /// Prefer paths through nodes advertising stable routes among equal-cost paths
+    stability_tiebreak: bool,
+
+    /// Longest route, in hops, that route computation will return
+    max_route_hops: Option<u8>,
+
+    /// Configured link cost to each static peer (peers not listed cost 1)
+    link_costs: HashMap<String, u32>,
+
+    /// This node's own ID, used as the route source by `find_route`
+    node_id: Option<String>,
+
+    /// Newest LSA accepted for flooding from each originator, and the peer it
+    /// came from, until a flood takes it
+    pending_floods: HashMap<String, (LinkStateAdvertisement, Option<String>)>,
+}
+
+impl RoutingTableInner {
+    /// Forget a direct peer and its per-peer state; see `RoutingTable::remove_peer`
+    fn remove_peer(&mut self, node_id: &str) -> Option<PeerInfo> {
+        self.peer_down_since.remove(node_id);
+        self.peer_epochs.remove(node_id);
+        self.peer_health.remove(node_id);
+        let removed = self.peers.remove(node_id);
+        if removed.as_ref().is_some_and(|peer| peer.status.is_up()) {
+            self.topology_changed_at = SystemTime::now();
+        }
+        removed
+    }
+}
+
+/// The status change of a peer forgotten or reset to Unknown by
+/// `apply_peer_diff`, if it wasn't Unknown already
+fn reset_event(peer: &PeerInfo) -> Option<TopologyEvent> {
+    (peer.status != PeerStatus::Unknown).then(|| TopologyEvent::PeerStatusChanged {
+        node_id: peer.node_id.clone(),
+        previous: peer.status,
+        status: PeerStatus::Unknown,
+    })
+}
+
+/// Changes made to the static peer set by `apply_peer_diff`
+#[derive(Debug, Default, PartialEq)]
+pub struct PeerDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Peers whose address changed
+    pub updated: Vec<String>,
+}
+
+impl PeerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// Which nodes a node can currently route to, from `reachability_report`
+#[derive(Debug, Default, PartialEq)]
+pub struct ReachabilityReport {
+    /// Known nodes with a route from the source
+    pub reachable: BTreeSet<String>,
+    /// Nodes with an LSA (or configured as a peer) but no route from the source
+    pub unreachable: BTreeSet<String>,
+    /// Nodes only named as some LSA's neighbor, with no LSA of their own yet
+    pub unknown: BTreeSet<String>,
+}
+
+/// A shortest route plus a backup for it, from `find_route_with_backup`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteWithBackup {
+    pub primary: Vec<String>,
+    /// Shares no intermediate node with `primary`, so no single relay
+    /// failure breaks both; `None` if the topology has no such path
+    pub backup: Option<Vec<String>>,
+}
+
+/// Route cost of a single hop over a link of cost 1
+const HOP_COST: usize = 1_000_000;
+
+/// Extra cost for passing through a node whose topology just changed
+/// Kept far below `HOP_COST` so it only separates paths of equal link cost.
+const MAX_INSTABILITY_COST: usize = 1_000;
+
+/// Tie-break cost for a node based on its advertised stability hint
+/// Halves after a minute of stability and keeps decaying from there;
+/// nodes that don't advertise a hint are treated as just changed.
+fn instability_cost(stability_hint: Option<u64>) -> usize {
+    match stability_hint {
+        Some(secs) => (MAX_INSTABILITY_COST as u64 * 60 / (60 + secs)) as usize,
+        None => MAX_INSTABILITY_COST,
+    }
+}
+
+/// Cost of a link to a Degraded peer: the configured cost divided by the
+/// peer's health score (at least `MIN_LINK_SCORE`), so a peer scoring 0.5
+/// costs twice as much
+fn degraded_link_cost(cost: u32, health_score: Option<f64>) -> u32 {
+    let score = health_score.unwrap_or(MIN_LINK_SCORE).max(MIN_LINK_SCORE);
+    (f64::from(cost) / score).ceil() as u32
+}
+
+/// How much newer an LSA's origination timestamp must be than the stored one
+/// for a lower sequence number to be taken as a restart rather than a delayed
+/// copy, when the epoch can't tell (e.g. originators that don't send one)
+const RESTART_TIMESTAMP_MARGIN: Duration = Duration::from_secs(5);
+
+/// Upper bound on the equal-cost routes returned by `find_routes_from`,
+/// since their number can grow exponentially in a grid-like mesh
+const MAX_EQUAL_COST_ROUTES: usize = 16;
+
+/// Check whether `lsa` was originated more than `RESTART_TIMESTAMP_MARGIN`
+/// after `existing`, by the originator's own clock
+fn originated_after_restart(lsa: &LinkStateAdvertisement, existing: &LinkStateAdvertisement) -> bool {
+    match (lsa.timestamp, existing.timestamp) {
+        (Some(new), Some(old)) => new
+            .duration_since(old)
+            .is_ok_and(|newer_by| newer_by > RESTART_TIMESTAMP_MARGIN),
+        _ => false,
+    }
 }
 
 /// Node for Dijkstra's algorithm priority queue
@@ -28,6 +214,8 @@ struct RoutingTableInner {
 struct DijkstraNode {
     node_id: String,
     distance: usize,
+    /// Links traversed to get here
+    hops: usize,
 }
 
 impl Ord for DijkstraNode {
@@ -35,6 +223,7 @@ impl Ord for DijkstraNode {
         // Reverse ordering for min-heap
         other.distance.cmp(&self.distance)
             .then_with(|| self.node_id.cmp(&other.node_id))
+            .then_with(|| other.hops.cmp(&self.hops))
     }
 }
 
@@ -44,21 +233,81 @@ impl PartialOrd for DijkstraNode {
     }
 }
 
+/// Generate a boot epoch from the current time in milliseconds
+///
+/// Epochs only need to differ across restarts of the same node, so wall
+/// clock time at startup is sufficient and needs no extra state on disk;
+/// they are never compared for order, so a clock stepping backwards across a
+/// restart does no harm.
+fn boot_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
 impl RoutingTable {
     /// Create a new empty routing table
     pub fn new() -> Self {
+        let link_costs = HashMap::new();
         Self {
             inner: Arc::new(RwLock::new(RoutingTableInner {
                 peers: HashMap::new(),
                 lsa_database: HashMap::new(),
                 own_lsa_sequence: 0,
+                epoch: boot_epoch(),
+                peer_epochs: HashMap::new(),
+                peer_health: HashMap::new(),
+                static_peers: HashSet::new(),
+                peer_down_since: HashMap::new(),
+                lsa_signer: None,
+                lsa_verifier: None,
+                require_signed_lsas: false,
+                lsa_flood_scope: None,
+                topology_changed_at: SystemTime::now(),
+                stability_tiebreak: false,
+                max_route_hops: None,
+                link_costs,
+                node_id: None,
+                pending_floods: HashMap::new(),
             })),
+            events: broadcast::channel(TOPOLOGY_EVENT_CAPACITY).0,
+            rng: RouteRng::default(),
         }
     }
 
-    /// Initialize routing table from configuration
-    pub fn from_config(peer_configs: Vec<PeerConfig>) -> Self {
+    /// Make randomized route selection repeatable by seeding its RNG
+    ///
+    /// Tables (and load balancers sharing their RNG) seeded alike make the
+    /// same choices in the same order, which keeps tests reproducible.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = RouteRng::seeded(seed);
+        self
+    }
+
+    /// RNG used for randomized route selection
+    pub fn rng(&self) -> &RouteRng {
+        &self.rng
+    }
+
+    /// Initialize routing table for node `node_id` from configuration
+    ///
+    /// A peer listing `node_id` itself is skipped (with a warning): it would
+    /// put a self-loop in the routing graph and have us health-check ourselves.
+    pub fn from_config(node_id: &str, peer_configs: Vec<PeerConfig>) -> Self {
         let mut peers = HashMap::new();
+        let now = SystemTime::now();
+        let peer_configs: Vec<PeerConfig> = peer_configs
+            .into_iter()
+            .filter(|peer| {
+                let is_self = peer.node_id == node_id;
+                if is_self {
+                    tracing::warn!("Ignoring peer '{}' at {}: it is this node itself", peer.node_id, peer.address);
+                }
+                !is_self
+            })
+            .collect();
+        let link_costs = peer_configs.iter().map(|p| (p.node_id.clone(), p.cost)).collect();
 
         for peer_config in peer_configs {
             let peer_info = PeerInfo {
@@ -66,19 +315,56 @@ impl RoutingTable {
                 address: peer_config.address,
                 status: PeerStatus::Unknown,
                 last_seen: None,
+                seconds_since_seen: None,
+                health_score: None,
             };
             peers.insert(peer_config.node_id, peer_info);
         }
 
+        let static_peers = peers.keys().cloned().collect();
+        let peer_down_since = peers.keys().map(|id| (id.clone(), now)).collect();
+
         Self {
             inner: Arc::new(RwLock::new(RoutingTableInner {
                 peers,
                 lsa_database: HashMap::new(),
                 own_lsa_sequence: 0,
+                epoch: boot_epoch(),
+                peer_epochs: HashMap::new(),
+                peer_health: HashMap::new(),
+                static_peers,
+                peer_down_since,
+                lsa_signer: None,
+                lsa_verifier: None,
+                require_signed_lsas: false,
+                lsa_flood_scope: None,
+                topology_changed_at: SystemTime::now(),
+                stability_tiebreak: false,
+                max_route_hops: None,
+                link_costs,
+                node_id: Some(node_id.to_string()),
+                pending_floods: HashMap::new(),
             })),
+            events: broadcast::channel(TOPOLOGY_EVENT_CAPACITY).0,
+            rng: RouteRng::default(),
         }
     }
 
+    /// Sender for topology events, for handing to components that subscribe later
+    pub fn topology_events(&self) -> broadcast::Sender<TopologyEvent> {
+        self.events.clone()
+    }
+
+    /// Subscribe to topology events published from now on
+    pub fn subscribe_topology(&self) -> broadcast::Receiver<TopologyEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish a topology event; having no subscribers is fine
+    fn publish(&self, event: TopologyEvent) {
+        let _ = self.events.send(event);
+    }
+
     /// Mark all peers as connected (temporary for testing until health checks are implemented)
     pub fn mark_all_connected(&self) {
         let mut inner = self.inner.write().unwrap();
@@ -86,22 +372,110 @@ impl RoutingTable {
             peer.status = PeerStatus::Connected;
             peer.last_seen = Some(SystemTime::now());
         }
+        inner.peer_down_since.clear();
     }
 
     /// Add or update a peer in the routing table
+    /// Peers added this way (rather than from config) are considered discovered
     pub fn add_peer(&self, peer: PeerInfo) {
         let mut inner = self.inner.write().unwrap();
+        if peer.status.is_up() {
+            inner.topology_changed_at = SystemTime::now();
+            inner.peer_down_since.remove(&peer.node_id);
+        } else {
+            inner
+                .peer_down_since
+                .entry(peer.node_id.clone())
+                .or_insert_with(SystemTime::now);
+        }
         inner.peers.insert(peer.node_id.clone(), peer);
     }
 
     /// Update peer status
     pub fn update_peer_status(&self, node_id: &str, status: PeerStatus) {
         let mut inner = self.inner.write().unwrap();
+        self.set_peer_status(&mut inner, node_id, status);
+    }
+
+    /// Record a health check of `node_id`: its round-trip time, or None if
+    /// it failed
+    ///
+    /// Updates the peer's health score and sets its status from the recent
+    /// checks (see `HealthHistory::status`). Returns the new status, or None
+    /// if `node_id` isn't a peer.
+    pub fn record_health_check(&self, node_id: &str, result: Option<Duration>) -> Option<PeerStatus> {
+        let mut inner = self.inner.write().unwrap();
+        if !inner.peers.contains_key(node_id) {
+            return None;
+        }
+
+        let health = inner.peer_health.entry(node_id.to_string()).or_default();
+        health.record(result);
+        let (score, status) = (health.score(), health.status());
+
+        if let Some(peer) = inner.peers.get_mut(node_id) {
+            peer.health_score = score;
+        }
+        self.set_peer_status(&mut inner, node_id, status);
+        Some(status)
+    }
+
+    /// Mark Connected or Degraded peers that haven't been seen for longer than `max_age` as Unknown
+    ///
+    /// Health checks, LSAs and messages from a peer refresh its `last_seen`, so
+    /// this catches a peer that went quiet between health checks. Peers that
+    /// have never been seen are left to the health check. Returns the node_ids
+    /// downgraded.
+    pub fn mark_stale_peers(&self, max_age: Duration) -> Vec<String> {
+        let now = SystemTime::now();
+        let mut inner = self.inner.write().unwrap();
+
+        let mut stale: Vec<String> = inner
+            .peers
+            .values()
+            .filter(|peer| peer.status.is_up())
+            .filter(|peer| {
+                peer.last_seen
+                    .and_then(|seen| now.duration_since(seen).ok())
+                    .is_some_and(|age| age > max_age)
+            })
+            .map(|peer| peer.node_id.clone())
+            .collect();
+        stale.sort();
+
+        for node_id in &stale {
+            self.set_peer_status(&mut inner, node_id, PeerStatus::Unknown);
+        }
+        stale
+    }
+
+    fn set_peer_status(&self, inner: &mut RoutingTableInner, node_id: &str, status: PeerStatus) {
         if let Some(peer) = inner.peers.get_mut(node_id) {
+            let previous = peer.status;
+            let link_changed = previous.is_up() != status.is_up();
             peer.status = status;
-            if status == PeerStatus::Connected {
+            if status.is_up() {
                 peer.last_seen = Some(SystemTime::now());
             }
+            if link_changed {
+                inner.topology_changed_at = SystemTime::now();
+            }
+            if status.is_up() {
+                inner.peer_down_since.remove(node_id);
+            } else {
+                inner
+                    .peer_down_since
+                    .entry(node_id.to_string())
+                    .or_insert_with(SystemTime::now);
+            }
+
+            if previous != status {
+                self.publish(TopologyEvent::PeerStatusChanged {
+                    node_id: node_id.to_string(),
+                    previous,
+                    status,
+                });
+            }
         }
     }
 
@@ -119,27 +493,180 @@ impl RoutingTable {
         inner.peers.get(node_id).cloned()
     }
 
-    /// Get all peers
+    /// Get all peers, with `seconds_since_seen` filled in
     pub fn get_all_peers(&self) -> Vec<PeerInfo> {
+        let now = SystemTime::now();
         let inner = self.inner.read().unwrap();
-        inner.peers.values().cloned().collect()
+        inner
+            .peers
+            .values()
+            .map(|peer| PeerInfo {
+                seconds_since_seen: peer
+                    .last_seen
+                    .map(|seen| now.duration_since(seen).unwrap_or_default().as_secs()),
+                ..peer.clone()
+            })
+            .collect()
     }
 
-    /// Get all connected peers
+    /// Get all peers whose link is up (Connected or Degraded)
     pub fn get_connected_peers(&self) -> Vec<PeerInfo> {
         let inner = self.inner.read().unwrap();
         inner
             .peers
             .values()
-            .filter(|p| p.status == PeerStatus::Connected)
+            .filter(|p| p.status.is_up())
             .cloned()
             .collect()
     }
 
     /// Remove a peer from the routing table
     pub fn remove_peer(&self, node_id: &str) -> Option<PeerInfo> {
+        self.inner.write().unwrap().remove_peer(node_id)
+    }
+
+    /// Bring the statically-configured peers in line with a new configuration
+    ///
+    /// New peers are added (status unknown until the next health check),
+    /// static peers missing from `peer_configs` are removed, and peers whose
+    /// address changed are reset to unknown. Peers discovered at runtime and
+    /// the LSA database are left untouched. A removed or reset peer that had
+    /// a known status publishes a `PeerStatusChanged` to Unknown.
+    pub fn apply_peer_diff(&self, peer_configs: &[PeerConfig]) -> PeerDiff {
+        let now = SystemTime::now();
+        let mut inner = self.inner.write().unwrap();
+        let mut diff = PeerDiff::default();
+        let mut events = Vec::new();
+
+        // As in `from_config`, a peer that is this node itself is ignored
+        let own_id = inner.node_id.clone();
+        let peer_configs: Vec<&PeerConfig> = peer_configs
+            .iter()
+            .filter(|peer| Some(&peer.node_id) != own_id.as_ref())
+            .collect();
+
+        let configured: HashSet<&str> = peer_configs.iter().map(|p| p.node_id.as_str()).collect();
+        let mut removed: Vec<String> = inner
+            .static_peers
+            .iter()
+            .filter(|node_id| !configured.contains(node_id.as_str()))
+            .cloned()
+            .collect();
+        removed.sort();
+
+        for node_id in &removed {
+            inner.static_peers.remove(node_id);
+            inner.link_costs.remove(node_id);
+            if let Some(peer) = inner.remove_peer(node_id) {
+                events.extend(reset_event(&peer));
+            }
+        }
+        diff.removed = removed;
+
+        for peer_config in peer_configs {
+            let unchanged = inner
+                .peers
+                .get(&peer_config.node_id)
+                .is_some_and(|peer| peer.address == peer_config.address);
+
+            if !unchanged {
+                match inner.peers.get(&peer_config.node_id) {
+                    Some(previous) => {
+                        events.extend(reset_event(previous));
+                        if previous.status.is_up() {
+                            inner.topology_changed_at = now;
+                        }
+                        diff.updated.push(peer_config.node_id.clone());
+                    }
+                    None => diff.added.push(peer_config.node_id.clone()),
+                }
+
+                inner.peers.insert(
+                    peer_config.node_id.clone(),
+                    PeerInfo {
+                        node_id: peer_config.node_id.clone(),
+                        address: peer_config.address.clone(),
+                        status: PeerStatus::Unknown,
+                        last_seen: None,
+                        seconds_since_seen: None,
+                        health_score: None,
+                    },
+                );
+                inner.peer_down_since.insert(peer_config.node_id.clone(), now);
+                inner.peer_epochs.remove(&peer_config.node_id);
+                inner.peer_health.remove(&peer_config.node_id);
+            }
+
+            inner.static_peers.insert(peer_config.node_id.clone());
+            inner.link_costs.insert(peer_config.node_id.clone(), peer_config.cost);
+        }
+        drop(inner);
+
+        for event in events {
+            self.publish(event);
+        }
+        diff
+    }
+
+    /// Remove peers that have been continuously down for longer than `max_down`
+    ///
+    /// "Down" means any status other than Connected or Degraded, including peers that have
+    /// never been reached. Statically-configured peers are only pruned when
+    /// `include_static` is set. Returns the node_ids that were removed.
+    pub fn prune_down_peers(&self, max_down: Duration, include_static: bool) -> Vec<String> {
+        let now = SystemTime::now();
+        let mut inner = self.inner.write().unwrap();
+
+        let expired: Vec<String> = inner
+            .peer_down_since
+            .iter()
+            .filter(|(node_id, _)| include_static || !inner.static_peers.contains(*node_id))
+            .filter(|(_, since)| now.duration_since(**since).unwrap_or_default() >= max_down)
+            .map(|(node_id, _)| node_id.clone())
+            .collect();
+
+        for node_id in &expired {
+            inner.remove_peer(node_id);
+        }
+
+        expired
+    }
+
+    /// Remove LSAs whose timestamp is older than `max_age`
+    ///
+    /// Originators re-advertise periodically, so an LSA that hasn't been
+    /// refreshed for `max_age` belongs to a node that is gone. LSAs without a
+    /// timestamp can't be aged and are kept. Returns the originators removed.
+    pub fn prune_stale_lsas(&self, max_age: Duration) -> Vec<String> {
+        let now = SystemTime::now();
         let mut inner = self.inner.write().unwrap();
-        inner.peers.remove(node_id)
+
+        let mut expired: Vec<String> = inner
+            .lsa_database
+            .values()
+            .filter(|lsa| {
+                lsa.timestamp
+                    .and_then(|ts| now.duration_since(ts).ok())
+                    .is_some_and(|age| age > max_age)
+            })
+            .map(|lsa| lsa.node_id.clone())
+            .collect();
+        expired.sort();
+
+        for node_id in &expired {
+            inner.lsa_database.remove(node_id);
+        }
+
+        if !expired.is_empty() {
+            inner.topology_changed_at = now;
+            for node_id in &expired {
+                self.publish(TopologyEvent::LsaExpired {
+                    node_id: node_id.clone(),
+                });
+            }
+        }
+
+        expired
     }
 
     /// Get the number of peers
@@ -148,43 +675,450 @@ impl RoutingTable {
         inner.peers.len()
     }
 
+    /// Whether an LSA from any node other than this one has been learned
+    pub fn routing_initialized(&self) -> bool {
+        let inner = self.inner.read().unwrap();
+        inner
+            .lsa_database
+            .keys()
+            .any(|node_id| inner.node_id.as_deref() != Some(node_id.as_str()))
+    }
+
+    /// Get this node's boot epoch
+    pub fn epoch(&self) -> u64 {
+        let inner = self.inner.read().unwrap();
+        inner.epoch
+    }
+
+    /// Record the boot epoch reported by a direct peer
+    /// Returns true if the peer was seen before with a different epoch (it restarted).
+    /// On restart the peer's stored LSA is dropped so its reset sequence
+    /// numbers are not mistaken for stale advertisements.
+    pub fn record_peer_epoch(&self, node_id: &str, epoch: u64) -> bool {
+        let mut inner = self.inner.write().unwrap();
+        let previous = inner.peer_epochs.insert(node_id.to_string(), epoch);
+
+        match previous {
+            Some(previous) if epoch != previous => {
+                inner.lsa_database.remove(node_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Sign all LSAs generated from now on with the given signer
+    pub fn set_lsa_signer(&self, signer: LsaSigner) {
+        let mut inner = self.inner.write().unwrap();
+        inner.lsa_signer = Some(Arc::new(signer));
+    }
+
+    /// Verify the signature of every signed LSA before accepting it
+    pub fn set_lsa_verifier(&self, verifier: LsaVerifier) {
+        let mut inner = self.inner.write().unwrap();
+        inner.lsa_verifier = Some(verifier);
+    }
+
+    /// Whether a verifier checks the signatures of accepted LSAs
+    pub fn verifies_lsa_signatures(&self) -> bool {
+        self.inner.read().unwrap().lsa_verifier.is_some()
+    }
+
+    /// Reject LSAs that carry no signature (signed LSAs are always verified)
+    pub fn set_require_signed_lsas(&self, required: bool) {
+        let mut inner = self.inner.write().unwrap();
+        inner.require_signed_lsas = required;
+    }
+
+    /// Limit how far our own LSAs are flooded (None floods to the whole mesh)
+    pub fn set_lsa_flood_scope(&self, scope: Option<u8>) {
+        let mut inner = self.inner.write().unwrap();
+        inner.lsa_flood_scope = scope;
+    }
+
+    /// Set this node's own ID so `find_route` can compute multi-hop routes
+    pub fn set_node_id(&self, node_id: &str) {
+        let mut inner = self.inner.write().unwrap();
+        inner.node_id = Some(node_id.to_string());
+    }
+
+    /// Use advertised stability hints to break ties between equal-hop routes
+    pub fn set_stability_tiebreak(&self, enabled: bool) {
+        let mut inner = self.inner.write().unwrap();
+        inner.stability_tiebreak = enabled;
+    }
+
+    /// Treat destinations only reachable over more than `max_hops` hops as
+    /// unreachable (None = no limit)
+    pub fn set_max_route_hops(&self, max_hops: Option<u8>) {
+        let mut inner = self.inner.write().unwrap();
+        inner.max_route_hops = max_hops;
+    }
+
+    fn max_route_hops(&self) -> Option<usize> {
+        self.inner.read().unwrap().max_route_hops.map(usize::from)
+    }
+
     /// Generate a new LSA for this node
+    /// The LSA is signed if an LSA signer has been configured
     pub fn generate_lsa(&self, node_id: &str) -> LinkStateAdvertisement {
         let mut inner = self.inner.write().unwrap();
-        inner.own_lsa_sequence += 1;
 
         // Get list of connected neighbors
         let neighbors: Vec<String> = inner
             .peers
             .values()
-            .filter(|p| p.status == PeerStatus::Connected)
+            .filter(|p| p.status.is_up())
             .map(|p| p.node_id.clone())
             .collect();
 
-        LinkStateAdvertisement {
+        Self::next_own_lsa(&mut inner, node_id, neighbors)
+    }
+
+    /// Generate an LSA with no neighbors, telling the mesh this node is leaving
+    /// Its higher sequence number supersedes our last real LSA everywhere.
+    pub fn generate_withdrawal_lsa(&self, node_id: &str) -> LinkStateAdvertisement {
+        let mut inner = self.inner.write().unwrap();
+        Self::next_own_lsa(&mut inner, node_id, Vec::new())
+    }
+
+    /// Build (and sign, if enabled) our next LSA advertising `neighbors`
+    fn next_own_lsa(inner: &mut RoutingTableInner, node_id: &str, neighbors: Vec<String>) -> LinkStateAdvertisement {
+        inner.own_lsa_sequence += 1;
+
+        let neighbor_costs: BTreeMap<String, u32> = neighbors
+            .iter()
+            .filter_map(|n| inner.link_costs.get(n).filter(|&&cost| cost != 1).map(|&cost| (n.clone(), cost)))
+            .collect();
+
+        let mut lsa = LinkStateAdvertisement {
             node_id: node_id.to_string(),
             neighbors,
+            neighbor_costs,
             sequence: inner.own_lsa_sequence,
+            epoch: inner.epoch,
             timestamp: Some(SystemTime::now()),
+            flood_ttl: inner.lsa_flood_scope,
+            signature: None,
+            signer_cert: None,
+            stability_hint: Some(
+                SystemTime::now()
+                    .duration_since(inner.topology_changed_at)
+                    .unwrap_or_default()
+                    .as_secs(),
+            ),
+        };
+
+        if let Some(signer) = &inner.lsa_signer
+            && let Err(e) = signer.sign(&mut lsa)
+        {
+            tracing::warn!("Failed to sign own LSA, sending unsigned: {}", e);
         }
+
+        lsa
     }
 
     /// Process a received LSA
     /// Returns true if the LSA was new or newer than what we had
+    /// (higher sequence number, or a different boot epoch after a restart).
+    /// Within one epoch, a lower sequence number is still accepted if the LSA
+    /// was originated clearly later than the stored one, since that means the
+    /// originator restarted without advertising a new epoch.
+    ///
+    /// An LSA failing the signature policy (see `try_process_lsa`) is logged
+    /// and not accepted.
     pub fn process_lsa(&self, lsa: LinkStateAdvertisement) -> bool {
+        let originator = lsa.node_id.clone();
+        self.try_process_lsa(lsa).unwrap_or_else(|e| {
+            tracing::warn!("Rejected LSA from {}: {:#}", originator, e);
+            false
+        })
+    }
+
+    /// `process_lsa`, reporting why an LSA was rejected
+    ///
+    /// Signed LSAs must verify if a verifier is set; unsigned ones are
+    /// rejected when signatures are required.
+    pub fn try_process_lsa(&self, lsa: LinkStateAdvertisement) -> Result<bool> {
+        self.accept_lsa(lsa, None)
+    }
+
+    /// Process an LSA received from `upstream` (None = unknown sender) and,
+    /// if it is accepted, make it the one `take_lsa_to_flood` returns for its
+    /// originator
+    ///
+    /// Acceptance and the flood decision happen under one write lock, so of
+    /// LSAs from one originator processed concurrently, a flood only ever
+    /// sends the newest. Fails like `try_process_lsa`.
+    pub fn process_lsa_for_flood(&self, lsa: LinkStateAdvertisement, upstream: Option<&str>) -> Result<bool> {
+        self.accept_lsa(lsa, Some(upstream))
+    }
+
+    /// Take the LSA waiting to be flooded from `originator`, with the peer it
+    /// came from
+    ///
+    /// Returns None once a flood has taken it, or if the stored LSA has since
+    /// changed (by `(epoch, sequence)`) without being queued for flooding.
+    pub fn take_lsa_to_flood(&self, originator: &str) -> Option<(LinkStateAdvertisement, Option<String>)> {
+        let mut inner = self.inner.write().unwrap();
+        let (lsa, upstream) = inner.pending_floods.remove(originator)?;
+        let stored = inner.lsa_database.get(originator)?;
+        (stored.epoch == lsa.epoch && stored.sequence == lsa.sequence).then_some((lsa, upstream))
+    }
+
+    /// `process_lsa`, queueing an accepted LSA for flooding when `flood_from`
+    /// is given
+    fn accept_lsa(&self, lsa: LinkStateAdvertisement, flood_from: Option<Option<&str>>) -> Result<bool> {
+        // Verify before the LSA can touch the database, without holding the lock
+        let (verifier, require_signed) = {
+            let inner = self.inner.read().unwrap();
+            (inner.lsa_verifier.clone(), inner.require_signed_lsas)
+        };
+        if lsa.signature.is_none() {
+            if require_signed {
+                anyhow::bail!("unsigned LSAs are not accepted");
+            }
+        } else if let Some(verifier) = verifier {
+            verifier.verify(&lsa)?;
+        }
+
         let mut inner = self.inner.write().unwrap();
 
         // Check if we already have an LSA from this node
         if let Some(existing_lsa) = inner.lsa_database.get(&lsa.node_id) {
-            // Only accept if sequence number is higher (newer)
-            if lsa.sequence <= existing_lsa.sequence {
-                return false;
+            // A different epoch means the originator restarted: accept
+            // regardless of sequence, since its counter started over from zero.
+            // Epochs are not ordered, so a clock that stepped backwards across
+            // the restart doesn't lock the originator out.
+            // Within the same epoch, only accept if sequence number is higher (newer)
+            if lsa.epoch == existing_lsa.epoch
+                && lsa.sequence <= existing_lsa.sequence
+                && !(lsa.sequence < existing_lsa.sequence && originated_after_restart(&lsa, existing_lsa))
+            {
+                return Ok(false);
             }
         }
 
-        // Store or update the LSA
+        // Periodic refreshes repeat the same neighbors; only real changes count
+        let neighbors_changed = inner
+            .lsa_database
+            .get(&lsa.node_id)
+            .is_none_or(|existing| existing.neighbors != lsa.neighbors);
+        if neighbors_changed {
+            inner.topology_changed_at = SystemTime::now();
+        }
+
+        let event = TopologyEvent::LsaAccepted {
+            node_id: lsa.node_id.clone(),
+            sequence: lsa.sequence,
+            neighbors_changed,
+        };
+
+        // Store or update the LSA, replacing any older one still waiting to flood
+        if let Some(upstream) = flood_from {
+            inner.pending_floods.insert(lsa.node_id.clone(), (lsa.clone(), upstream.map(String::from)));
+        }
         inner.lsa_database.insert(lsa.node_id.clone(), lsa);
-        true
+        self.publish(event);
+        Ok(true)
+    }
+
+    /// Check whether a node appears anywhere in our view of the network:
+    /// as a direct peer, as an LSA originator, or as a neighbor in some LSA
+    pub fn is_known_node(&self, node_id: &str) -> bool {
+        let inner = self.inner.read().unwrap();
+        inner.peers.contains_key(node_id)
+            || inner.lsa_database.contains_key(node_id)
+            || inner
+                .lsa_database
+                .values()
+                .any(|lsa| lsa.neighbors.iter().any(|n| n == node_id))
+    }
+
+    /// Find one-directional neighbor claims in the LSA database
+    ///
+    /// Returns `(node, neighbor)` pairs where `node`'s LSA lists `neighbor`
+    /// but `neighbor`'s LSA doesn't list `node` back, sorted by node. Claims
+    /// about nodes we have no LSA from can't be checked and are left out.
+    pub fn asymmetry_report(&self) -> Vec<(String, String)> {
+        let inner = self.inner.read().unwrap();
+        let mut asymmetries: Vec<(String, String)> = inner
+            .lsa_database
+            .values()
+            .flat_map(|lsa| {
+                lsa.neighbors.iter().filter_map(|neighbor| {
+                    let reverse = inner.lsa_database.get(neighbor)?;
+                    (!reverse.neighbors.contains(&lsa.node_id))
+                        .then(|| (lsa.node_id.clone(), neighbor.clone()))
+                })
+            })
+            .collect();
+
+        asymmetries.sort();
+        asymmetries
+    }
+
+    /// Find destinations whose route back to `source` differs from the route
+    /// `source` takes to them
+    ///
+    /// Forward routes are computed from our view; routes back are computed
+    /// from `remote_lsas`, another node's LSA database (e.g. fetched from its
+    /// GET /topology/lsa), as the destinations themselves would. Passing our
+    /// own LSAs shows asymmetry from link costs and tie-breaks alone.
+    pub fn detect_asymmetry(&self, source: &str, remote_lsas: &[LinkStateAdvertisement]) -> Vec<PathAsymmetry> {
+        let remote = RoutingTable::new();
+        {
+            let inner = self.inner.read().unwrap();
+            remote.set_stability_tiebreak(inner.stability_tiebreak);
+            remote.set_max_route_hops(inner.max_route_hops);
+        }
+        for lsa in remote_lsas {
+            remote.process_lsa(lsa.clone());
+        }
+
+        let mut asymmetries: Vec<PathAsymmetry> = self
+            .route_snapshot(source)
+            .into_iter()
+            .filter_map(|(destination, route)| {
+                let forward: Vec<String> = std::iter::once(source.to_string()).chain(route).collect();
+                let reverse = remote.find_route_from(&destination, source).map(|route| {
+                    std::iter::once(destination.clone()).chain(route).rev().collect::<Vec<String>>()
+                });
+                (reverse.as_ref() != Some(&forward)).then_some(PathAsymmetry { destination, forward, reverse })
+            })
+            .collect();
+
+        asymmetries.sort_by(|a, b| a.destination.cmp(&b.destination));
+        asymmetries
+    }
+
+    /// Split the nodes we know of into those `source` can reach and those it
+    /// can't, to tell whether the topology has converged
+    ///
+    /// Nodes only heard of as someone's neighbor are reported as unknown,
+    /// since their own view of their links hasn't arrived yet.
+    pub fn reachability_report(&self, source: &str) -> ReachabilityReport {
+        let (known, mentioned): (HashSet<String>, HashSet<String>) = {
+            let inner = self.inner.read().unwrap();
+            let known = inner.peers.keys().chain(inner.lsa_database.keys()).cloned().collect();
+            let mentioned = inner.lsa_database.values().flat_map(|lsa| lsa.neighbors.iter()).cloned().collect();
+            (known, mentioned)
+        };
+
+        let mut report = ReachabilityReport::default();
+        for node in known.iter().filter(|node| node.as_str() != source) {
+            if self.find_route_from(source, node).is_some() {
+                report.reachable.insert(node.clone());
+            } else {
+                report.unreachable.insert(node.clone());
+            }
+        }
+        report.unknown = mentioned
+            .into_iter()
+            .filter(|node| node != source && !known.contains(node))
+            .collect();
+        report
+    }
+
+    /// Compute the current route from `source` to every other known node
+    /// Unreachable nodes are left out.
+    pub fn route_snapshot(&self, source: &str) -> RouteSnapshot {
+        let nodes: HashSet<String> = {
+            let inner = self.inner.read().unwrap();
+            inner
+                .peers
+                .keys()
+                .chain(inner.lsa_database.keys())
+                .chain(inner.lsa_database.values().flat_map(|lsa| lsa.neighbors.iter()))
+                .filter(|node| node.as_str() != source)
+                .cloned()
+                .collect()
+        };
+
+        nodes
+            .into_iter()
+            .filter_map(|node| {
+                let route = self.find_route_from(source, &node)?;
+                Some((node, route))
+            })
+            .collect()
+    }
+
+    /// Summarize the peer table, link-state database and routes from `source`
+    pub fn compute_stats(&self, source: &str) -> RoutingStats {
+        let (total_peers, connected_peers, lsa_count) = {
+            let inner = self.inner.read().unwrap();
+            let connected = inner.peers.values().filter(|peer| peer.status.is_up()).count();
+            (inner.peers.len(), connected, inner.lsa_database.len())
+        };
+
+        let routes = self.route_snapshot(source);
+        let average_path_length = (!routes.is_empty())
+            .then(|| routes.values().map(Vec::len).sum::<usize>() as f64 / routes.len() as f64);
+
+        RoutingStats {
+            total_peers,
+            connected_peers,
+            lsa_count,
+            reachable_nodes: routes.len(),
+            unreachable_nodes: self.reachability_report(source).unreachable.len(),
+            average_path_length,
+        }
+    }
+
+    /// Snapshot of the link-state database and peer table, sorted by node_id
+    /// so views from different nodes can be diffed directly
+    pub fn get_topology_snapshot(&self) -> TopologySnapshot {
+        let inner = self.inner.read().unwrap();
+
+        let mut nodes: Vec<TopologyNode> = inner
+            .lsa_database
+            .values()
+            .map(|lsa| TopologyNode {
+                node_id: lsa.node_id.clone(),
+                neighbors: lsa.neighbors.clone(),
+                neighbor_costs: lsa.neighbor_costs.clone(),
+                sequence: lsa.sequence,
+                epoch: lsa.epoch,
+                timestamp: lsa.timestamp,
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+
+        let mut peers: Vec<PeerInfo> = inner.peers.values().cloned().collect();
+        peers.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+
+        TopologySnapshot { nodes, peers }
+    }
+
+    /// Load another node's topology snapshot (e.g. fetched from its
+    /// GET /topology) as our link-state database and peer statuses
+    ///
+    /// Snapshots carry no signatures, so when signed LSAs are required none
+    /// of the snapshot's nodes are accepted.
+    pub fn load_topology_snapshot(&self, snapshot: TopologySnapshot) {
+        for node in snapshot.nodes {
+            self.process_lsa(LinkStateAdvertisement {
+                node_id: node.node_id,
+                neighbors: node.neighbors,
+                neighbor_costs: node.neighbor_costs,
+                sequence: node.sequence,
+                epoch: node.epoch,
+                timestamp: node.timestamp,
+                flood_ttl: None,
+                signature: None,
+                signer_cert: None,
+                stability_hint: None,
+            });
+        }
+
+        for peer in snapshot.peers {
+            if self.get_peer(&peer.node_id).is_some() {
+                self.update_peer_status(&peer.node_id, peer.status);
+            } else {
+                self.add_peer(peer);
+            }
+        }
     }
 
     /// Get all LSAs in the database (for forwarding)
@@ -195,118 +1129,482 @@ impl RoutingTable {
 
     /// Find a route to a destination node using Dijkstra's algorithm
     /// Returns a vector of node_ids representing the path (excluding source)
+    /// Multi-hop routes are only found once `set_node_id` has been called.
     pub fn find_route(&self, destination: &str) -> Option<Vec<String>> {
         let inner = self.inner.read().unwrap();
 
         // Check if destination is a direct connected peer (fast path)
-        if let Some(peer) = inner.peers.get(destination) {
-            if peer.status == PeerStatus::Connected {
-                return Some(vec![destination.to_string()]);
-            }
+        if let Some(peer) = inner.peers.get(destination)
+            && peer.status == PeerStatus::Connected
+        {
+            return Some(vec![destination.to_string()]);
         }
 
+        // A degraded direct link is still used, unless the LSAs show a cheaper path
+        let direct = inner
+            .peers
+            .get(destination)
+            .filter(|peer| peer.status.is_up())
+            .map(|_| vec![destination.to_string()]);
+
         // Use link-state database to find multi-hop route
         // This will only work if we have received LSAs from other nodes
         if inner.lsa_database.is_empty() {
-            return None;
+            return direct;
         }
 
-        // We need to know our own node_id to run Dijkstra
-        // Extract it from one of our LSAs in the database
-        // For now, return None if we don't have complete topology
-        // This will be properly set when we integrate with the server
+        // Dijkstra needs a source; without our own node_id there is none
+        let Some(source) = inner.node_id.clone() else {
+            return direct;
+        };
+        drop(inner);
 
-        None // Placeholder - will be implemented with proper node_id context
+        self.find_route_from(&source, destination).or(direct)
     }
 
     /// Find route using Dijkstra's algorithm given a source node
     pub fn find_route_from(&self, source: &str, destination: &str) -> Option<Vec<String>> {
-        let inner = self.inner.read().unwrap();
-
         if source == destination {
             return Some(vec![]);
         }
 
-        // Build adjacency list from LSA database
-        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        let (graph, penalties) = self.topology_graph(source);
+        shortest_path(&graph, &penalties, source, destination, self.max_route_hops())
+    }
 
-        // Add direct peers to graph
-        for (node_id, peer) in &inner.peers {
-            if peer.status == PeerStatus::Connected {
-                graph.entry(source.to_string())
-                    .or_insert_with(Vec::new)
-                    .push(node_id.clone());
-            }
+    /// Find every equal-cost shortest route from `source` to `destination`
+    /// Routes are sorted, and at most `MAX_EQUAL_COST_ROUTES` are returned.
+    pub fn find_routes_from(&self, source: &str, destination: &str) -> Vec<Vec<String>> {
+        if source == destination {
+            return vec![vec![]];
         }
 
-        // Add LSA information to graph
-        for lsa in inner.lsa_database.values() {
-            graph.entry(lsa.node_id.clone())
-                .or_insert_with(Vec::new)
-                .extend(lsa.neighbors.clone());
+        let (graph, penalties) = self.topology_graph(source);
+        let mut routes = all_shortest_paths(&graph, &penalties, source, destination);
+        if let Some(max_hops) = self.max_route_hops() {
+            routes.retain(|route| route.len() <= max_hops);
+            // Every cheapest route is too long; take the cheapest one that fits
+            if routes.is_empty() {
+                routes.extend(shortest_path(&graph, &penalties, source, destination, Some(max_hops)));
+            }
         }
+        routes
+    }
 
-        // Run Dijkstra's algorithm
-        let mut distances: HashMap<String, usize> = HashMap::new();
-        let mut previous: HashMap<String, String> = HashMap::new();
-        let mut visited: HashSet<String> = HashSet::new();
-        let mut heap = BinaryHeap::new();
+    /// Find the shortest route from `source` to `destination` together with a
+    /// node-disjoint backup
+    pub fn find_route_with_backup(&self, source: &str, destination: &str) -> Option<RouteWithBackup> {
+        let primary = self.find_route_from(source, destination)?;
+        let backup = self.find_disjoint_route(source, destination, &primary);
+        Some(RouteWithBackup { primary, backup })
+    }
 
-        distances.insert(source.to_string(), 0);
-        heap.push(DijkstraNode {
-            node_id: source.to_string(),
-            distance: 0,
-        });
+    /// Find the shortest route from `source` to `destination` that avoids
+    /// every intermediate node of `primary`
+    ///
+    /// The backup is found by removing those nodes and running Dijkstra again.
+    /// If `primary` is a direct link, only that link is removed.
+    pub fn find_disjoint_route(&self, source: &str, destination: &str, primary: &[String]) -> Option<Vec<String>> {
+        if source == destination || primary.is_empty() {
+            return None;
+        }
 
-        while let Some(DijkstraNode { node_id, distance }) = heap.pop() {
-            if visited.contains(&node_id) {
-                continue;
+        let (mut graph, penalties) = self.topology_graph(source);
+        let intermediates: HashSet<&str> = primary[..primary.len() - 1].iter().map(String::as_str).collect();
+        if intermediates.is_empty() {
+            if let Some(links) = graph.get_mut(source) {
+                links.retain(|(neighbor, _)| neighbor != destination);
+            }
+        } else {
+            graph.retain(|node_id, _| !intermediates.contains(node_id.as_str()));
+            for links in graph.values_mut() {
+                links.retain(|(neighbor, _)| !intermediates.contains(neighbor.as_str()));
             }
+        }
 
-            if node_id == destination {
-                // Reconstruct path
-                let mut path = vec![];
-                let mut current = destination.to_string();
+        shortest_path(&graph, &penalties, source, destination, self.max_route_hops())
+    }
 
-                while current != source {
-                    path.push(current.clone());
-                    match previous.get(&current) {
-                        Some(prev) => current = prev.clone(),
-                        None => return None, // Path broken
-                    }
+    /// Compute the route `source` would take to `destination`, purely from
+    /// our own view of the topology (for what-if analysis)
+    ///
+    /// Unlike `find_route_from`, our connected peers stay attached to
+    /// `local_node_id`, so any node in the mesh can be used as the source.
+    pub fn find_route_whatif(&self, local_node_id: &str, source: &str, destination: &str) -> Option<Vec<String>> {
+        if source == destination {
+            return Some(vec![]);
+        }
+
+        let (graph, penalties) = self.topology_graph(local_node_id);
+        shortest_path(&graph, &penalties, source, destination, self.max_route_hops())
+    }
+
+    /// Cost of the link `from` -> `to` as used by route computation, with
+    /// our connected peers attached to `local_node_id`
+    pub fn link_cost(&self, local_node_id: &str, from: &str, to: &str) -> Option<u32> {
+        let (graph, _) = self.topology_graph(local_node_id);
+        graph
+            .get(from)?
+            .iter()
+            .find(|(neighbor, _)| neighbor == to)
+            .map(|(_, cost)| *cost)
+    }
+
+    /// Build an adjacency list from our connected peers (as links of
+    /// `local_node_id`) and the two-way links in the LSA database, with the
+    /// cost of each link, along with the tie-break penalty for entering each
+    /// node
+    fn topology_graph(&self, local_node_id: &str) -> (Graph, HashMap<String, usize>) {
+        let inner = self.inner.read().unwrap();
+        let mut graph: Graph = HashMap::new();
+
+        // Add direct peers to graph, making degraded links dearer
+        for (node_id, peer) in &inner.peers {
+            if peer.status.is_up() {
+                let mut cost = inner.link_costs.get(node_id).copied().unwrap_or(1);
+                if peer.status == PeerStatus::Degraded {
+                    cost = degraded_link_cost(cost, peer.health_score);
                 }
+                graph.entry(local_node_id.to_string())
+                    .or_default()
+                    .push((node_id.clone(), cost));
+            }
+        }
+
+        // Add LSA information to graph, skipping links the other end denies:
+        // if the neighbor has an LSA, it must list the originator too
+        for lsa in inner.lsa_database.values() {
+            let confirmed = |neighbor: &String| {
+                inner
+                    .lsa_database
+                    .get(neighbor)
+                    .is_none_or(|reverse| reverse.neighbors.contains(&lsa.node_id))
+            };
 
-                path.reverse();
-                return Some(path);
+            graph.entry(lsa.node_id.clone())
+                .or_default()
+                .extend(lsa.neighbors.iter().filter(|n| confirmed(n)).map(|n| {
+                    (n.clone(), lsa.neighbor_costs.get(n).copied().unwrap_or(1))
+                }));
+        }
+
+        let mut penalties = HashMap::new();
+        if inner.stability_tiebreak {
+            for lsa in inner.lsa_database.values() {
+                penalties.insert(lsa.node_id.clone(), instability_cost(lsa.stability_hint));
             }
+        }
+
+        (graph, penalties)
+    }
+}
 
-            visited.insert(node_id.clone());
+/// Adjacency list of (neighbor, link cost) per node
+type Graph = HashMap<String, Vec<(String, u32)>>;
+
+/// Run Dijkstra's algorithm over an adjacency list
+/// Traversing a link costs `HOP_COST` per unit of link cost, plus the
+/// node's entry in `penalties` if any.
+/// Paths longer than `max_hops` are never expanded.
+/// Returns the path from `source` to `destination` (excluding source)
+fn shortest_path(
+    graph: &Graph,
+    penalties: &HashMap<String, usize>,
+    source: &str,
+    destination: &str,
+    max_hops: Option<usize>,
+) -> Option<Vec<String>> {
+    // With a hop limit, a node reached cheaply over many hops may be a dead
+    // end where a costlier but shorter arrival isn't, so each node is
+    // searched once per hop count; without one, once in total
+    let layer = |hops: usize| if max_hops.is_some() { hops } else { 0 };
+
+    let mut distances: HashMap<(String, usize), usize> = HashMap::new();
+    let mut previous: HashMap<(String, usize), String> = HashMap::new();
+    let mut visited: HashSet<(String, usize)> = HashSet::new();
+    let mut heap = BinaryHeap::new();
+
+    distances.insert((source.to_string(), 0), 0);
+    heap.push(DijkstraNode {
+        node_id: source.to_string(),
+        distance: 0,
+        hops: 0,
+    });
+
+    while let Some(DijkstraNode { node_id, distance, hops }) = heap.pop() {
+        let state = (node_id, layer(hops));
+        if visited.contains(&state) {
+            continue;
+        }
 
-            // Check neighbors
-            if let Some(neighbors) = graph.get(&node_id) {
-                for neighbor in neighbors {
-                    if visited.contains(neighbor) {
-                        continue;
+        if state.0 == destination {
+            // Reconstruct path
+            let mut path = vec![];
+            let mut current = state;
+            let mut hops = hops;
+
+            while current.0 != source {
+                hops = hops.saturating_sub(1);
+                match previous.get(&current) {
+                    Some(prev) => {
+                        let prev = (prev.clone(), layer(hops));
+                        path.push(std::mem::replace(&mut current, prev).0);
                     }
+                    None => return None, // Path broken
+                }
+            }
 
-                    let new_distance = distance + 1;
-                    let is_shorter = distances
-                        .get(neighbor)
-                        .map_or(true, |&current| new_distance < current);
-
-                    if is_shorter {
-                        distances.insert(neighbor.clone(), new_distance);
-                        previous.insert(neighbor.clone(), node_id.clone());
-                        heap.push(DijkstraNode {
-                            node_id: neighbor.clone(),
-                            distance: new_distance,
-                        });
-                    }
+            path.reverse();
+            return Some(path);
+        }
+
+        visited.insert(state.clone());
+        if max_hops.is_some_and(|max_hops| hops >= max_hops) {
+            continue;
+        }
+
+        // Check neighbors
+        if let Some(neighbors) = graph.get(&state.0) {
+            for (neighbor, link_cost) in neighbors {
+                let next = (neighbor.clone(), layer(hops + 1));
+                if visited.contains(&next) {
+                    continue;
+                }
+
+                let new_distance = distance
+                    + *link_cost as usize * HOP_COST
+                    + penalties.get(neighbor).copied().unwrap_or(0);
+                let is_shorter = distances
+                    .get(&next)
+                    .is_none_or(|&current| new_distance < current);
+
+                if is_shorter {
+                    distances.insert(next.clone(), new_distance);
+                    previous.insert(next, state.0.clone());
+                    heap.push(DijkstraNode {
+                        node_id: neighbor.clone(),
+                        distance: new_distance,
+                        hops: hops + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Like `shortest_path`, but returns every path of minimal cost
+fn all_shortest_paths(
+    graph: &Graph,
+    penalties: &HashMap<String, usize>,
+    source: &str,
+    destination: &str,
+) -> Vec<Vec<String>> {
+    let mut distances: HashMap<String, usize> = HashMap::new();
+    // Every predecessor through which a node is reached at its minimal cost
+    let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut heap = BinaryHeap::new();
+
+    distances.insert(source.to_string(), 0);
+    heap.push(DijkstraNode {
+        node_id: source.to_string(),
+        distance: 0,
+        hops: 0,
+    });
+
+    while let Some(DijkstraNode { node_id, distance, hops }) = heap.pop() {
+        // Links cost at least HOP_COST, so the destination's predecessors are final
+        if node_id == destination {
+            break;
+        }
+        if !visited.insert(node_id.clone()) {
+            continue;
+        }
+
+        for (neighbor, link_cost) in graph.get(&node_id).into_iter().flatten() {
+            if visited.contains(neighbor) {
+                continue;
+            }
+
+            let new_distance = distance
+                + *link_cost as usize * HOP_COST
+                + penalties.get(neighbor).copied().unwrap_or(0);
+            match distances.get(neighbor) {
+                Some(&current) if new_distance > current => {}
+                Some(&current) if new_distance == current => {
+                    predecessors.entry(neighbor.clone()).or_default().push(node_id.clone());
+                }
+                _ => {
+                    distances.insert(neighbor.clone(), new_distance);
+                    predecessors.insert(neighbor.clone(), vec![node_id.clone()]);
+                    heap.push(DijkstraNode {
+                        node_id: neighbor.clone(),
+                        distance: new_distance,
+                        hops: hops + 1,
+                    });
                 }
             }
         }
+    }
+
+    if !predecessors.contains_key(destination) {
+        return vec![];
+    }
+
+    // Walk the predecessor DAG back from the destination
+    let mut paths = Vec::new();
+    let mut partial = vec![vec![destination.to_string()]];
+    while let Some(path) = partial.pop() {
+        if paths.len() == MAX_EQUAL_COST_ROUTES {
+            break;
+        }
+        let last = path.last().unwrap();
+        for prev in predecessors.get(last).into_iter().flatten() {
+            let mut extended = path.clone();
+            if prev != source {
+                extended.push(prev.clone());
+                partial.push(extended);
+            } else {
+                extended.reverse();
+                paths.push(extended);
+            }
+        }
+    }
+
+    paths.truncate(MAX_EQUAL_COST_ROUTES);
+    paths.sort();
+    paths
+}
+
+/// How a `LoadBalancer` picks among equal-cost next hops
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceStrategy {
+    /// Take each next hop in turn, per destination
+    RoundRobin,
+    /// Pick a next hop at random
+    Random,
+    /// Pick the next hop that has gone unused the longest
+    LeastRecentlyUsed,
+}
+
+/// Random number generator for route selection (SplitMix64)
+///
+/// Seeded from the process's hash randomness by default; clones share one
+/// state, so a seeded generator yields one reproducible sequence.
+#[derive(Clone)]
+pub struct RouteRng {
+    state: Arc<Mutex<u64>>,
+}
+
+impl RouteRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(seed)),
+        }
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Index in `0..len`; `len` must be non-zero
+    pub fn index(&self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
+
+impl Default for RouteRng {
+    fn default() -> Self {
+        Self::seeded(std::collections::hash_map::RandomState::new().build_hasher().finish())
+    }
+}
+
+/// Spreads traffic to a destination over the direct peers that start one of
+/// its equal-cost routes, keeping a usage count per next hop
+#[derive(Clone, Default)]
+pub struct LoadBalancer {
+    inner: Arc<Mutex<LoadBalancerInner>>,
+    rng: RouteRng,
+}
+
+#[derive(Default)]
+struct LoadBalancerInner {
+    /// Round-robin position per destination
+    cursors: HashMap<String, usize>,
+    /// Times each next hop was selected
+    usage: HashMap<String, u64>,
+    /// Selection tick at which each next hop was last used
+    last_used: HashMap<String, u64>,
+    tick: u64,
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw random choices from `rng`, usually the routing table's
+    pub fn with_rng(rng: RouteRng) -> Self {
+        Self {
+            inner: Arc::default(),
+            rng,
+        }
+    }
+
+    /// Choose one of `routes` (as returned by `find_routes_from`) to `destination`
+    ///
+    /// Routes are grouped by next hop, so balancing happens across direct
+    /// peers; for a given next hop the first of its routes is used.
+    pub fn select(&self, strategy: BalanceStrategy, destination: &str, routes: Vec<Vec<String>>) -> Option<Vec<String>> {
+        let mut candidates: Vec<Vec<String>> = Vec::new();
+        for route in routes {
+            let Some(next_hop) = route.first() else {
+                continue;
+            };
+            if !candidates.iter().any(|c| c[0] == *next_hop) {
+                candidates.push(route);
+            }
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let index = match strategy {
+            BalanceStrategy::RoundRobin => {
+                let cursor = inner.cursors.entry(destination.to_string()).or_default();
+                let index = *cursor % candidates.len();
+                *cursor = cursor.wrapping_add(1);
+                index
+            }
+            BalanceStrategy::Random => self.rng.index(candidates.len()),
+            BalanceStrategy::LeastRecentlyUsed => candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, route)| inner.last_used.get(&route[0]).copied())
+                .map(|(index, _)| index)
+                .unwrap_or(0),
+        };
 
-        None
+        let route = candidates.swap_remove(index);
+        inner.tick += 1;
+        let tick = inner.tick;
+        *inner.usage.entry(route[0].clone()).or_default() += 1;
+        inner.last_used.insert(route[0].clone(), tick);
+        Some(route)
+    }
+
+    /// How many times `next_hop` has been selected
+    pub fn usage(&self, next_hop: &str) -> u64 {
+        self.inner.lock().unwrap().usage.get(next_hop).copied().unwrap_or(0)
     }
 }
 
@@ -319,6 +1617,7 @@ impl Default for RoutingTable {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::PeerProtocol;
 
     #[test]
     fn test_new_routing_table() {
@@ -335,6 +1634,8 @@ mod tests {
             address: "127.0.0.1:8002".to_string(),
             status: PeerStatus::Connected,
             last_seen: Some(SystemTime::now()),
+            seconds_since_seen: None,
+            health_score: None,
         };
 
         table.add_peer(peer.clone());
@@ -351,20 +1652,43 @@ mod tests {
             PeerConfig {
                 node_id: "gateway-b".to_string(),
                 address: "127.0.0.1:8002".to_string(),
+                protocol: PeerProtocol::default(),
+                cost: 1,
             },
             PeerConfig {
                 node_id: "gateway-c".to_string(),
                 address: "127.0.0.1:8003".to_string(),
+                protocol: PeerProtocol::default(),
+                cost: 1,
             },
         ];
 
-        let table = RoutingTable::from_config(peers);
+        let table = RoutingTable::from_config("gateway-a", peers);
         assert_eq!(table.peer_count(), 2);
 
         let peer_b = table.get_peer("gateway-b").unwrap();
         assert_eq!(peer_b.status, PeerStatus::Unknown);
     }
 
+    #[test]
+    fn test_self_peer_is_excluded() {
+        let peers = vec![
+            peer_config("gateway-a", "127.0.0.1:8001"),
+            peer_config("gateway-b", "127.0.0.1:8002"),
+        ];
+
+        let table = RoutingTable::from_config("gateway-a", peers.clone());
+        assert_eq!(table.peer_count(), 1);
+        assert!(table.get_peer("gateway-a").is_none());
+        table.mark_all_connected();
+        assert_eq!(table.generate_lsa("gateway-a").neighbors, vec!["gateway-b"]);
+
+        // Nor is it added back by a reload
+        let diff = table.apply_peer_diff(&peers);
+        assert!(diff.added.is_empty());
+        assert!(table.get_peer("gateway-a").is_none());
+    }
+
     #[test]
     fn test_update_peer_status() {
         let table = RoutingTable::new();
@@ -374,6 +1698,8 @@ mod tests {
             address: "127.0.0.1:8002".to_string(),
             status: PeerStatus::Unknown,
             last_seen: None,
+            seconds_since_seen: None,
+            health_score: None,
         };
 
         table.add_peer(peer);
@@ -393,6 +1719,8 @@ mod tests {
             address: "127.0.0.1:8002".to_string(),
             status: PeerStatus::Connected,
             last_seen: Some(SystemTime::now()),
+            seconds_since_seen: None,
+            health_score: None,
         });
 
         table.add_peer(PeerInfo {
@@ -400,6 +1728,8 @@ mod tests {
             address: "127.0.0.1:8003".to_string(),
             status: PeerStatus::Disconnected,
             last_seen: None,
+            seconds_since_seen: None,
+            health_score: None,
         });
 
         let connected = table.get_connected_peers();
@@ -416,6 +1746,8 @@ mod tests {
             address: "127.0.0.1:8002".to_string(),
             status: PeerStatus::Connected,
             last_seen: Some(SystemTime::now()),
+            seconds_since_seen: None,
+            health_score: None,
         });
 
         let route = table.find_route("gateway-b");
@@ -435,6 +1767,8 @@ mod tests {
             address: "127.0.0.1:8002".to_string(),
             status: PeerStatus::Connected,
             last_seen: Some(SystemTime::now()),
+            seconds_since_seen: None,
+            health_score: None,
         });
 
         assert_eq!(table.peer_count(), 1);
@@ -443,4 +1777,975 @@ mod tests {
         assert!(removed.is_some());
         assert_eq!(table.peer_count(), 0);
     }
+
+    fn lsa(node_id: &str, neighbors: &[&str], sequence: u64) -> LinkStateAdvertisement {
+        LinkStateAdvertisement {
+            node_id: node_id.to_string(),
+            neighbors: neighbors.iter().map(|n| n.to_string()).collect(),
+            neighbor_costs: BTreeMap::new(),
+            sequence,
+            epoch: 1,
+            timestamp: Some(SystemTime::now()),
+            flood_ttl: None,
+            signature: None,
+            signer_cert: None,
+            stability_hint: None,
+        }
+    }
+
+    #[test]
+    fn test_concurrent_lsas_only_newest_floods() {
+        let table = RoutingTable::new();
+
+        // Fire interleaved sequences from the same originator concurrently;
+        // each accepted one asks for a flood, as the LSA handler does
+        let sequences: Vec<u64> = vec![3, 7, 1, 8, 2, 6, 5, 4];
+        let handles: Vec<_> = sequences
+            .into_iter()
+            .map(|seq| {
+                let table = table.clone();
+                std::thread::spawn(move || {
+                    let accepted = table.process_lsa_for_flood(lsa("gateway-b", &["gateway-a"], seq), Some("gateway-c")).unwrap();
+                    accepted.then(|| table.take_lsa_to_flood("gateway-b")).flatten()
+                })
+            })
+            .collect();
+
+        // However the threads interleave, no LSA floods twice, and the last
+        // flood to be taken is the highest sequence
+        let mut flooded: Vec<u64> = handles
+            .into_iter()
+            .filter_map(|h| h.join().unwrap())
+            .map(|(lsa, _)| lsa.sequence)
+            .collect();
+        flooded.sort();
+        let taken = flooded.len();
+        flooded.dedup();
+        assert_eq!(flooded.len(), taken, "{:?}", flooded);
+        assert_eq!(flooded.last(), Some(&8));
+        assert!(table.take_lsa_to_flood("gateway-b").is_none());
+
+        // With every LSA accepted before any flood runs, only the highest floods
+        let table = RoutingTable::new();
+        for seq in [3, 7, 1, 8, 2, 6, 5, 4] {
+            table.process_lsa_for_flood(lsa("gateway-b", &["gateway-a"], seq), Some("gateway-c")).unwrap();
+        }
+        let (flooded, upstream) = table.take_lsa_to_flood("gateway-b").unwrap();
+        assert_eq!(flooded.sequence, 8);
+        assert_eq!(upstream.as_deref(), Some("gateway-c"));
+        assert!(table.take_lsa_to_flood("gateway-b").is_none());
+    }
+
+    #[test]
+    fn test_superseded_lsa_is_not_flooded() {
+        let table = RoutingTable::new();
+        assert!(table.process_lsa_for_flood(lsa("gateway-b", &["gateway-a"], 5), None).unwrap());
+
+        // Replaced without a flood request (e.g. by a database sync)
+        assert!(table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 6)));
+        assert!(table.take_lsa_to_flood("gateway-b").is_none());
+
+        // A restart's LSA with the same sequence but a new epoch isn't the pending one either
+        assert!(table.process_lsa_for_flood(lsa("gateway-b", &["gateway-a"], 7), None).unwrap());
+        assert!(table.process_lsa(LinkStateAdvertisement { epoch: 2, ..lsa("gateway-b", &["gateway-a"], 7) }));
+        assert!(table.take_lsa_to_flood("gateway-b").is_none());
+    }
+
+    #[test]
+    fn test_signed_lsa_verified_by_process_lsa() {
+        let pki = crate::test_util::TestPki::generate(&["gateway-b"]);
+        let table = RoutingTable::new();
+        table.set_lsa_verifier(LsaVerifier::from_ca_files(&[pki.ca_path()]).unwrap());
+        table.set_require_signed_lsas(true);
+
+        let signer = LsaSigner::from_files(pki.cert_path("gateway-b"), pki.key_path("gateway-b")).unwrap();
+        let mut signed = lsa("gateway-b", &["gateway-a"], 1);
+        signer.sign(&mut signed).unwrap();
+
+        // Tampered in transit: rejected before it reaches the database
+        let mut tampered = signed.clone();
+        tampered.neighbors.push("gateway-evil".to_string());
+        assert!(table.try_process_lsa(tampered.clone()).is_err());
+        assert!(!table.process_lsa(tampered));
+        assert!(table.get_all_lsas().is_empty());
+
+        // Unsigned: rejected while signatures are required
+        assert!(table.try_process_lsa(lsa("gateway-b", &["gateway-a"], 2)).is_err());
+
+        // Round trip through the wire format, as a flooded LSA arrives
+        let received: LinkStateAdvertisement = serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+        assert!(table.try_process_lsa(received).unwrap());
+        assert_eq!(table.get_all_lsas()[0].neighbors, vec!["gateway-a"]);
+    }
+
+    #[test]
+    fn test_topology_snapshot_rejected_when_signatures_required() {
+        let snapshot = TopologySnapshot {
+            nodes: vec![TopologyNode {
+                node_id: "gateway-b".to_string(),
+                neighbors: vec!["gateway-a".to_string()],
+                neighbor_costs: BTreeMap::new(),
+                sequence: 1,
+                epoch: 1,
+                timestamp: None,
+            }],
+            peers: Vec::new(),
+        };
+
+        let table = RoutingTable::new();
+        table.set_require_signed_lsas(true);
+        table.load_topology_snapshot(snapshot.clone());
+        assert!(table.get_all_lsas().is_empty());
+
+        let table = RoutingTable::new();
+        table.load_topology_snapshot(snapshot);
+        assert_eq!(table.get_all_lsas().len(), 1);
+    }
+
+    #[test]
+    fn test_restarted_node_lsa_accepted_despite_lower_sequence() {
+        let table = RoutingTable::new();
+
+        let before_restart = lsa("gateway-b", &["gateway-a"], 500);
+        assert!(table.process_lsa(before_restart));
+
+        // After a restart the sequence starts over but the epoch is newer
+        let mut after_restart = lsa("gateway-b", &["gateway-c"], 1);
+        after_restart.epoch = 2;
+        assert!(table.process_lsa(after_restart));
+
+        let stored = table.get_all_lsas();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].epoch, 2);
+        assert_eq!(stored[0].sequence, 1);
+        assert_eq!(stored[0].neighbors, vec!["gateway-c"]);
+
+        // Once on the new epoch, normal sequence ordering applies again
+        let mut stale = lsa("gateway-b", &["gateway-a"], 1);
+        stale.epoch = 2;
+        assert!(!table.process_lsa(stale));
+    }
+
+    #[test]
+    fn test_restart_without_new_epoch_detected_by_timestamp() {
+        let table = RoutingTable::new();
+        let now = SystemTime::now();
+
+        let mut before_restart = lsa("gateway-b", &["gateway-a"], 500);
+        before_restart.timestamp = Some(now - Duration::from_secs(120));
+        assert!(table.process_lsa(before_restart));
+
+        // A delayed copy from before the restart is still rejected
+        let mut delayed = lsa("gateway-b", &["gateway-c"], 499);
+        delayed.timestamp = Some(now - Duration::from_secs(121));
+        assert!(!table.process_lsa(delayed));
+
+        // Same epoch, sequence started over, but originated well after
+        let mut after_restart = lsa("gateway-b", &["gateway-c"], 1);
+        after_restart.timestamp = Some(now);
+        assert!(table.process_lsa(after_restart));
+        assert_eq!(table.get_all_lsas()[0].sequence, 1);
+
+        // Normal sequence ordering applies from there
+        assert!(!table.process_lsa(lsa("gateway-b", &["gateway-a"], 1)));
+        assert!(table.process_lsa(lsa("gateway-b", &["gateway-a"], 2)));
+    }
+
+    #[test]
+    fn test_lsa_from_lower_epoch_accepted_after_clock_step_back() {
+        let table = RoutingTable::new();
+
+        let mut before_restart = lsa("gateway-b", &["gateway-a"], 900);
+        before_restart.epoch = 2_000;
+        assert!(table.process_lsa(before_restart));
+
+        // The node restarted with its clock set back, so its new epoch is lower
+        let mut after_restart = lsa("gateway-b", &["gateway-c"], 1);
+        after_restart.epoch = 1_000;
+        assert!(table.process_lsa(after_restart));
+        assert_eq!(table.get_all_lsas()[0].neighbors, vec!["gateway-c"]);
+
+        // Within the new epoch, sequence ordering applies as usual
+        let mut repeat = lsa("gateway-b", &["gateway-a"], 1);
+        repeat.epoch = 1_000;
+        assert!(!table.process_lsa(repeat));
+    }
+
+    #[test]
+    fn test_record_peer_epoch_detects_restart() {
+        let table = RoutingTable::new();
+
+        assert!(!table.record_peer_epoch("gateway-b", 1));
+        assert!(!table.record_peer_epoch("gateway-b", 1));
+
+        assert!(table.process_lsa(lsa("gateway-b", &["gateway-a"], 42)));
+        assert!(table.record_peer_epoch("gateway-b", 2));
+
+        // Stored LSA from the previous boot is discarded
+        assert!(table.get_all_lsas().is_empty());
+    }
+
+    #[test]
+    fn test_generated_lsa_carries_epoch() {
+        let table = RoutingTable::new();
+        let lsa = table.generate_lsa("gateway-a");
+        assert_eq!(lsa.epoch, table.epoch());
+        assert!(lsa.epoch > 0);
+    }
+
+    #[test]
+    fn test_is_known_node() {
+        let table = RoutingTable::new();
+        table.add_peer(PeerInfo {
+            node_id: "gateway-b".to_string(),
+            address: "127.0.0.1:8002".to_string(),
+            status: PeerStatus::Disconnected,
+            last_seen: None,
+            seconds_since_seen: None,
+            health_score: None,
+        });
+        table.process_lsa(lsa("gateway-c", &["gateway-d"], 1));
+
+        assert!(table.is_known_node("gateway-b"));
+        assert!(table.is_known_node("gateway-c"));
+        assert!(table.is_known_node("gateway-d"));
+        assert!(!table.is_known_node("gateway-z"));
+    }
+
+    fn discovered_peer(node_id: &str, status: PeerStatus) -> PeerInfo {
+        PeerInfo {
+            node_id: node_id.to_string(),
+            address: "127.0.0.1:9000".to_string(),
+            status,
+            last_seen: None,
+            seconds_since_seen: None,
+            health_score: None,
+        }
+    }
+
+    #[test]
+    fn test_long_down_discovered_peer_is_pruned() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-x", PeerStatus::Unknown));
+        table.add_peer(discovered_peer("gateway-y", PeerStatus::Connected));
+
+        // Not down for long enough yet
+        assert!(table.prune_down_peers(Duration::from_secs(3600), false).is_empty());
+        assert_eq!(table.peer_count(), 2);
+
+        std::thread::sleep(Duration::from_millis(20));
+        let pruned = table.prune_down_peers(Duration::from_millis(10), false);
+        assert_eq!(pruned, vec!["gateway-x"]);
+        assert!(table.get_peer("gateway-x").is_none());
+
+        // Connected peers are never pruned
+        assert!(table.get_peer("gateway-y").is_some());
+    }
+
+    #[test]
+    fn test_peer_not_seen_recently_becomes_unknown() {
+        let table = RoutingTable::new();
+        let mut quiet = discovered_peer("gateway-x", PeerStatus::Connected);
+        quiet.last_seen = Some(SystemTime::now() - Duration::from_secs(120));
+        table.add_peer(quiet);
+        let mut fresh = discovered_peer("gateway-y", PeerStatus::Connected);
+        fresh.last_seen = Some(SystemTime::now());
+        table.add_peer(fresh);
+        table.add_peer(discovered_peer("gateway-z", PeerStatus::Connected));
+
+        let staleness: HashMap<String, Option<u64>> = table
+            .get_all_peers()
+            .into_iter()
+            .map(|peer| (peer.node_id, peer.seconds_since_seen))
+            .collect();
+        assert!(staleness["gateway-x"].is_some_and(|secs| (120..125).contains(&secs)));
+        assert_eq!(staleness["gateway-y"], Some(0));
+        assert_eq!(staleness["gateway-z"], None);
+
+        let mut events = table.subscribe_topology();
+        assert_eq!(table.mark_stale_peers(Duration::from_secs(60)), vec!["gateway-x"]);
+        assert_eq!(table.get_peer("gateway-x").unwrap().status, PeerStatus::Unknown);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(TopologyEvent::PeerStatusChanged { status: PeerStatus::Unknown, .. })
+        ));
+
+        // Recently seen and never-seen peers are left to the health check
+        assert_eq!(table.get_peer("gateway-y").unwrap().status, PeerStatus::Connected);
+        assert_eq!(table.get_peer("gateway-z").unwrap().status, PeerStatus::Connected);
+        assert!(table.mark_stale_peers(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn test_reconnect_resets_down_timer() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-x", PeerStatus::Disconnected));
+
+        std::thread::sleep(Duration::from_millis(20));
+        table.update_peer_status("gateway-x", PeerStatus::Connected);
+        table.update_peer_status("gateway-x", PeerStatus::Disconnected);
+
+        assert!(table.prune_down_peers(Duration::from_millis(10), false).is_empty());
+        assert!(table.get_peer("gateway-x").is_some());
+    }
+
+    #[test]
+    fn test_static_peers_exempt_from_pruning() {
+        let table = RoutingTable::from_config("gateway-a", vec![PeerConfig {
+            node_id: "gateway-b".to_string(),
+            address: "127.0.0.1:8002".to_string(),
+            protocol: PeerProtocol::default(),
+            cost: 1,
+        }]);
+
+        assert!(table.prune_down_peers(Duration::ZERO, false).is_empty());
+        assert_eq!(table.peer_count(), 1);
+
+        assert_eq!(table.prune_down_peers(Duration::ZERO, true), vec!["gateway-b"]);
+        assert_eq!(table.peer_count(), 0);
+    }
+
+    #[test]
+    fn test_generated_lsa_carries_flood_scope() {
+        let table = RoutingTable::new();
+        assert_eq!(table.generate_lsa("gateway-a").flood_ttl, None);
+
+        table.set_lsa_flood_scope(Some(3));
+        assert_eq!(table.generate_lsa("gateway-a").flood_ttl, Some(3));
+    }
+
+    #[test]
+    fn test_route_snapshot_covers_reachable_nodes() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-e"], 1));
+
+        let routes = table.route_snapshot("gateway-a");
+        assert_eq!(routes["gateway-b"], vec!["gateway-b"]);
+        assert_eq!(routes["gateway-c"], vec!["gateway-b", "gateway-c"]);
+        // Known but unreachable nodes, and ourselves, are left out
+        assert_eq!(routes.len(), 2);
+    }
+
+    #[test]
+    fn test_reachability_report_with_partition() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-g", PeerStatus::Disconnected));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-b"], 1));
+        // A partition the rest of the mesh has heard of but can't reach
+        table.process_lsa(lsa("gateway-d", &["gateway-e"], 1));
+        table.process_lsa(lsa("gateway-e", &["gateway-d", "gateway-f"], 1));
+
+        let report = table.reachability_report("gateway-a");
+        let set = |nodes: &[&str]| nodes.iter().map(|n| n.to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(report.reachable, set(&["gateway-b", "gateway-c"]));
+        assert_eq!(report.unreachable, set(&["gateway-d", "gateway-e", "gateway-g"]));
+        assert_eq!(report.unknown, set(&["gateway-f"]));
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let table = RoutingTable::new();
+        assert_eq!(table.compute_stats("gateway-a").average_path_length, None);
+
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-g", PeerStatus::Disconnected));
+        // b and c one and two hops away, d three hops via c; e is cut off
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-b", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-c"], 1));
+        table.process_lsa(lsa("gateway-e", &[], 1));
+
+        let stats = table.compute_stats("gateway-a");
+        assert_eq!(stats.total_peers, 2);
+        assert_eq!(stats.connected_peers, 1);
+        assert_eq!(stats.lsa_count, 4);
+        assert_eq!(stats.reachable_nodes, 3);
+        assert_eq!(stats.unreachable_nodes, 2);
+        assert_eq!(stats.average_path_length, Some(2.0));
+    }
+
+    #[test]
+    fn test_whatif_route_from_remote_source() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-d", PeerStatus::Connected));
+        // Linear mesh as seen from gateway-a: c - b - a - d
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-b"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-a"], 1));
+
+        let route = table.find_route_whatif("gateway-a", "gateway-c", "gateway-d").unwrap();
+        assert_eq!(route, vec!["gateway-b", "gateway-a", "gateway-d"]);
+
+        assert!(table.find_route_whatif("gateway-a", "gateway-c", "gateway-z").is_none());
+    }
+
+    fn peer_config(node_id: &str, address: &str) -> PeerConfig {
+        PeerConfig {
+            node_id: node_id.to_string(),
+            address: address.to_string(),
+            protocol: PeerProtocol::default(),
+            cost: 1,
+        }
+    }
+
+    #[test]
+    fn test_apply_peer_diff() {
+        let table = RoutingTable::from_config("gateway-a", vec![
+            peer_config("gateway-b", "127.0.0.1:8002"),
+            peer_config("gateway-c", "127.0.0.1:8003"),
+        ]);
+        table.add_peer(discovered_peer("gateway-x", PeerStatus::Connected));
+        table.update_peer_status("gateway-b", PeerStatus::Connected);
+        table.process_lsa(lsa("gateway-c", &["gateway-d"], 1));
+
+        let diff = table.apply_peer_diff(&[
+            peer_config("gateway-b", "127.0.0.1:8002"),
+            peer_config("gateway-c", "10.0.0.3:8003"),
+            peer_config("gateway-d", "127.0.0.1:8004"),
+        ]);
+
+        assert_eq!(diff.added, vec!["gateway-d"]);
+        assert_eq!(diff.updated, vec!["gateway-c"]);
+        assert!(diff.removed.is_empty());
+        // Unchanged peers keep their state; runtime peers and LSAs are kept
+        assert_eq!(table.get_peer("gateway-b").unwrap().status, PeerStatus::Connected);
+        assert_eq!(table.get_peer("gateway-c").unwrap().address, "10.0.0.3:8003");
+        assert!(table.get_peer("gateway-x").is_some());
+        assert_eq!(table.get_all_lsas().len(), 1);
+
+        let diff = table.apply_peer_diff(&[peer_config("gateway-b", "127.0.0.1:8002")]);
+        assert_eq!(diff.removed, vec!["gateway-c", "gateway-d"]);
+        assert_eq!(table.peer_count(), 2);
+    }
+
+    #[test]
+    fn test_apply_peer_diff_publishes_dropped_links() {
+        let table = RoutingTable::from_config("gateway-a", vec![
+            peer_config("gateway-b", "127.0.0.1:8002"),
+            peer_config("gateway-c", "127.0.0.1:8003"),
+        ]);
+        table.update_peer_status("gateway-b", PeerStatus::Connected);
+        table.update_peer_status("gateway-c", PeerStatus::Connected);
+        let changed_at = table.inner.read().unwrap().topology_changed_at;
+        let mut events = table.subscribe_topology();
+
+        // gateway-b moves, gateway-c is dropped: both links are gone
+        table.apply_peer_diff(&[peer_config("gateway-b", "10.0.0.2:8002")]);
+
+        let link_down = |node_id: &str| TopologyEvent::PeerStatusChanged {
+            node_id: node_id.to_string(),
+            previous: PeerStatus::Connected,
+            status: PeerStatus::Unknown,
+        };
+        let mut published = vec![events.try_recv().unwrap(), events.try_recv().unwrap()];
+        published.sort_by_key(|event| format!("{:?}", event));
+        assert_eq!(published, vec![link_down("gateway-b"), link_down("gateway-c")]);
+        assert!(events.try_recv().is_err());
+        assert!(table.inner.read().unwrap().topology_changed_at > changed_at);
+        assert!(!table.inner.read().unwrap().link_costs.contains_key("gateway-c"));
+
+        // Unknown peers have no status to lose
+        table.apply_peer_diff(&[peer_config("gateway-b", "10.0.0.9:8002")]);
+        assert!(events.try_recv().is_err());
+    }
+
+    fn lsa_with_stability(node_id: &str, neighbors: &[&str], sequence: u64, stability_hint: u64) -> LinkStateAdvertisement {
+        LinkStateAdvertisement {
+            stability_hint: Some(stability_hint),
+            ..lsa(node_id, neighbors, sequence)
+        }
+    }
+
+    #[test]
+    fn test_stability_tiebreak_prefers_stable_path() {
+        // Diamond: a -> {b, c} -> d
+        let table = RoutingTable::new();
+        table.set_stability_tiebreak(true);
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-c", PeerStatus::Connected));
+
+        table.process_lsa(lsa_with_stability("gateway-b", &["gateway-a", "gateway-d"], 1, 5));
+        table.process_lsa(lsa_with_stability("gateway-c", &["gateway-a", "gateway-d"], 1, 3600));
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-c", "gateway-d"]);
+
+        table.process_lsa(lsa_with_stability("gateway-b", &["gateway-a", "gateway-d"], 2, 3600));
+        table.process_lsa(lsa_with_stability("gateway-c", &["gateway-a", "gateway-d"], 2, 5));
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-b", "gateway-d"]);
+    }
+
+    #[test]
+    fn test_find_routes_from_returns_equal_cost_paths() {
+        // Diamond: a -> {b, c} -> d, plus a longer detour a -> e -> f -> d
+        let table = RoutingTable::new();
+        table.process_lsa(lsa("gateway-a", &["gateway-b", "gateway-c", "gateway-e"], 1));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-a", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-b", "gateway-c", "gateway-f"], 1));
+        table.process_lsa(lsa("gateway-e", &["gateway-a", "gateway-f"], 1));
+        table.process_lsa(lsa("gateway-f", &["gateway-e", "gateway-d"], 1));
+
+        let routes = table.find_routes_from("gateway-a", "gateway-d");
+        assert_eq!(routes, vec![vec!["gateway-b", "gateway-d"], vec!["gateway-c", "gateway-d"]]);
+        assert!(routes.contains(&table.find_route_from("gateway-a", "gateway-d").unwrap()));
+
+        // A costlier link removes its path from the set
+        table.process_lsa(LinkStateAdvertisement {
+            neighbor_costs: BTreeMap::from([("gateway-d".to_string(), 3)]),
+            ..lsa("gateway-c", &["gateway-a", "gateway-d"], 2)
+        });
+        assert_eq!(table.find_routes_from("gateway-a", "gateway-d"), vec![vec!["gateway-b", "gateway-d"]]);
+        assert!(table.find_routes_from("gateway-a", "gateway-x").is_empty());
+    }
+
+    #[test]
+    fn test_max_route_hops_rejects_long_paths() {
+        // a - b - c - d is cheapest; a - x - d is shorter but its links cost 5
+        let table = RoutingTable::new();
+        table.process_lsa(lsa("gateway-a", &["gateway-b", "gateway-x"], 1));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-b", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-c"], 1));
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-b", "gateway-c", "gateway-d"]);
+
+        table.set_max_route_hops(Some(2));
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d"), None);
+        assert!(table.find_routes_from("gateway-a", "gateway-d").is_empty());
+        assert_eq!(table.find_route_from("gateway-a", "gateway-c").unwrap(), vec!["gateway-b", "gateway-c"]);
+
+        // A costlier path within the limit is taken instead
+        table.process_lsa(LinkStateAdvertisement {
+            neighbor_costs: BTreeMap::from([("gateway-d".to_string(), 5)]),
+            ..lsa("gateway-x", &["gateway-a", "gateway-d"], 1)
+        });
+        table.process_lsa(lsa("gateway-d", &["gateway-c", "gateway-x"], 2));
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-x", "gateway-d"]);
+        assert_eq!(table.find_routes_from("gateway-a", "gateway-d"), vec![vec!["gateway-x", "gateway-d"]]);
+
+        table.set_max_route_hops(None);
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-b", "gateway-c", "gateway-d"]);
+    }
+
+    #[test]
+    fn test_backup_route_is_node_disjoint() {
+        // a - b - d is shortest; a - c - e - d shares no relay with it
+        let table = RoutingTable::new();
+        table.process_lsa(lsa("gateway-a", &["gateway-b", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-d", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-a", "gateway-b", "gateway-e"], 1));
+        table.process_lsa(lsa("gateway-e", &["gateway-c", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-b", "gateway-e"], 1));
+
+        let routes = table.find_route_with_backup("gateway-a", "gateway-d").unwrap();
+        assert_eq!(routes.primary, vec!["gateway-b", "gateway-d"]);
+        assert_eq!(routes.backup, Some(vec!["gateway-c".to_string(), "gateway-e".to_string(), "gateway-d".to_string()]));
+
+        // A direct link's backup only has to avoid that link
+        let routes = table.find_route_with_backup("gateway-a", "gateway-b").unwrap();
+        assert_eq!(routes.primary, vec!["gateway-b"]);
+        assert_eq!(routes.backup, Some(vec!["gateway-c".to_string(), "gateway-b".to_string()]));
+
+        // Every path to gateway-e's stub neighbor runs through gateway-e
+        table.process_lsa(lsa("gateway-e", &["gateway-c", "gateway-d", "gateway-f"], 2));
+        table.process_lsa(lsa("gateway-f", &["gateway-e"], 1));
+        let routes = table.find_route_with_backup("gateway-a", "gateway-f").unwrap();
+        assert_eq!(routes.primary, vec!["gateway-c", "gateway-e", "gateway-f"]);
+        assert_eq!(routes.backup, None);
+    }
+
+    #[test]
+    fn test_stability_never_outweighs_hop_count() {
+        // a - b - d is short but b is unstable; a - c - e - d is longer and stable
+        let table = RoutingTable::new();
+        table.set_stability_tiebreak(true);
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-c", PeerStatus::Connected));
+        table.process_lsa(lsa_with_stability("gateway-b", &["gateway-d"], 1, 0));
+        table.process_lsa(lsa_with_stability("gateway-c", &["gateway-e"], 1, 86400));
+        table.process_lsa(lsa_with_stability("gateway-e", &["gateway-d"], 1, 86400));
+
+        assert_eq!(table.find_route_from("gateway-a", "gateway-d").unwrap(), vec!["gateway-b", "gateway-d"]);
+    }
+
+    #[test]
+    fn test_refreshed_lsa_keeps_stability() {
+        let table = RoutingTable::new();
+        table.process_lsa(lsa("gateway-b", &["gateway-c"], 1));
+        let changed_at = table.inner.read().unwrap().topology_changed_at;
+
+        // Same neighbors with a newer sequence is a refresh, not a change
+        assert!(table.process_lsa(lsa("gateway-b", &["gateway-c"], 2)));
+        assert_eq!(table.inner.read().unwrap().topology_changed_at, changed_at);
+
+        assert!(table.generate_lsa("gateway-a").stability_hint.is_some());
+    }
+
+    #[test]
+    fn test_accepted_lsa_publishes_one_event() {
+        let table = RoutingTable::new();
+        let mut events = table.subscribe_topology();
+
+        assert!(table.process_lsa(lsa("gateway-b", &["gateway-c"], 1)));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TopologyEvent::LsaAccepted {
+                node_id: "gateway-b".to_string(),
+                sequence: 1,
+                neighbors_changed: true,
+            }
+        );
+        assert!(events.try_recv().is_err());
+
+        // Duplicates are not published
+        assert!(!table.process_lsa(lsa("gateway-b", &["gateway-c"], 1)));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_peer_status_change_publishes_event() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Unknown));
+        let mut events = table.subscribe_topology();
+
+        table.update_peer_status("gateway-b", PeerStatus::Connected);
+        table.update_peer_status("gateway-b", PeerStatus::Connected);
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TopologyEvent::PeerStatusChanged {
+                node_id: "gateway-b".to_string(),
+                previous: PeerStatus::Unknown,
+                status: PeerStatus::Connected,
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_asymmetry_report_lists_one_way_claims() {
+        let table = RoutingTable::new();
+        table.process_lsa(lsa("gateway-a", &["gateway-b", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-b", &["gateway-a"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-d"], 1));
+
+        // gateway-d has no LSA, so its side of the link can't be judged
+        assert_eq!(
+            table.asymmetry_report(),
+            vec![("gateway-a".to_string(), "gateway-c".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_link_costs_prefer_cheaper_longer_path() {
+        // gateway-b is one expensive hop from gateway-e; gateway-c/d are three cheap ones
+        let table = RoutingTable::from_config("gateway-a", vec![
+            PeerConfig {
+                cost: 10,
+                ..peer_config("gateway-b", "127.0.0.1:8002")
+            },
+            peer_config("gateway-c", "127.0.0.1:8003"),
+        ]);
+        table.update_peer_status("gateway-b", PeerStatus::Connected);
+        table.update_peer_status("gateway-c", PeerStatus::Connected);
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-e"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-a", "gateway-d"], 1));
+        table.process_lsa(lsa("gateway-d", &["gateway-c", "gateway-e"], 1));
+
+        assert_eq!(
+            table.find_route_from("gateway-a", "gateway-e"),
+            Some(vec!["gateway-c".to_string(), "gateway-d".to_string(), "gateway-e".to_string()])
+        );
+
+        // Our own LSA advertises the non-default cost
+        let own = table.generate_lsa("gateway-a");
+        assert_eq!(own.neighbor_costs.get("gateway-b"), Some(&10));
+        assert!(!own.neighbor_costs.contains_key("gateway-c"));
+    }
+
+    #[test]
+    fn test_degraded_peer_costs_more_to_route_through() {
+        let table = RoutingTable::from_config("gateway-a", vec![
+            peer_config("gateway-b", "127.0.0.1:8002"),
+            peer_config("gateway-c", "127.0.0.1:8003"),
+        ]);
+        table.record_health_check("gateway-b", Some(Duration::from_millis(5)));
+        table.record_health_check("gateway-c", Some(Duration::from_millis(5)));
+        assert_eq!(table.find_route("gateway-b"), Some(vec!["gateway-b".to_string()]));
+
+        // gateway-b flaps: 3 of its last 10 checks passed, the latest among them
+        for _ in 0..7 {
+            table.record_health_check("gateway-b", None);
+        }
+        for _ in 0..2 {
+            table.record_health_check("gateway-b", Some(Duration::from_millis(5)));
+        }
+        let status = table.record_health_check("gateway-b", Some(Duration::from_millis(5)));
+        assert_eq!(status, Some(PeerStatus::Degraded));
+        let peer = table.get_peer("gateway-b").unwrap();
+        assert_eq!(peer.health_score, Some(0.3));
+
+        // Still a link, but reached more cheaply through gateway-c once that path is known
+        assert_eq!(table.find_route("gateway-b"), Some(vec!["gateway-b".to_string()]));
+        assert!(table.generate_lsa("gateway-a").neighbors.contains(&"gateway-b".to_string()));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-a", "gateway-b"], 1));
+        assert_eq!(
+            table.find_route("gateway-b"),
+            Some(vec!["gateway-c".to_string(), "gateway-b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_advertised_link_costs_used_for_remote_links() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.process_lsa(LinkStateAdvertisement {
+            neighbor_costs: BTreeMap::from([("gateway-d".to_string(), 5)]),
+            ..lsa("gateway-b", &["gateway-c", "gateway-d"], 1)
+        });
+        table.process_lsa(lsa("gateway-c", &["gateway-b", "gateway-d"], 1));
+
+        assert_eq!(
+            table.find_route_from("gateway-a", "gateway-d"),
+            Some(vec!["gateway-b".to_string(), "gateway-c".to_string(), "gateway-d".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_detect_asymmetry_finds_different_route_back() {
+        // A square whose link costs differ by direction: a -> c is cheapest
+        // via b, but c -> a is cheapest via d
+        let with_costs = |node_id: &str, costs: &[(&str, u32)]| LinkStateAdvertisement {
+            neighbor_costs: costs.iter().map(|(n, cost)| (n.to_string(), *cost)).collect(),
+            ..lsa(node_id, &costs.iter().map(|(n, _)| *n).collect::<Vec<_>>(), 1)
+        };
+        let table = RoutingTable::new();
+        table.process_lsa(with_costs("gateway-a", &[("gateway-b", 1), ("gateway-d", 2)]));
+        table.process_lsa(with_costs("gateway-b", &[("gateway-a", 1), ("gateway-c", 1)]));
+        table.process_lsa(with_costs("gateway-c", &[("gateway-b", 5), ("gateway-d", 1)]));
+        table.process_lsa(with_costs("gateway-d", &[("gateway-a", 1), ("gateway-c", 2)]));
+
+        let path = |nodes: &[&str]| nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            table.detect_asymmetry("gateway-a", &table.get_all_lsas()),
+            vec![PathAsymmetry {
+                destination: "gateway-c".to_string(),
+                forward: path(&["gateway-a", "gateway-b", "gateway-c"]),
+                reverse: Some(path(&["gateway-a", "gateway-d", "gateway-c"])),
+            }]
+        );
+
+        // In a remote view that has lost the a - d link, c comes back the
+        // way we go, but d now has to go the long way round
+        let remote: Vec<LinkStateAdvertisement> = table
+            .get_all_lsas()
+            .into_iter()
+            .filter(|lsa| lsa.node_id != "gateway-a" && lsa.node_id != "gateway-d")
+            .chain([with_costs("gateway-a", &[("gateway-b", 1)]), with_costs("gateway-d", &[("gateway-c", 2)])])
+            .collect();
+        assert_eq!(
+            table.detect_asymmetry("gateway-a", &remote),
+            vec![PathAsymmetry {
+                destination: "gateway-d".to_string(),
+                forward: path(&["gateway-a", "gateway-d"]),
+                reverse: Some(path(&["gateway-a", "gateway-b", "gateway-c", "gateway-d"])),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_route_uses_own_node_id() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(lsa("gateway-c", &["gateway-b"], 1));
+
+        // Without a node_id there is no source to route from
+        assert_eq!(table.find_route("gateway-c"), None);
+
+        table.set_node_id("gateway-a");
+        assert_eq!(
+            table.find_route("gateway-c"),
+            Some(vec!["gateway-b".to_string(), "gateway-c".to_string()])
+        );
+        // Direct peers still take the fast path
+        assert_eq!(table.find_route("gateway-b"), Some(vec!["gateway-b".to_string()]));
+    }
+
+    #[test]
+    fn test_prune_stale_lsas_removes_old_entries() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        table.process_lsa(LinkStateAdvertisement {
+            timestamp: Some(SystemTime::now() - Duration::from_secs(200)),
+            ..lsa("gateway-c", &["gateway-b", "gateway-d"], 1)
+        });
+        assert!(table.find_route_from("gateway-a", "gateway-d").is_some());
+        let mut events = table.subscribe_topology();
+
+        assert_eq!(table.prune_stale_lsas(Duration::from_secs(90)), vec!["gateway-c"]);
+        assert_eq!(table.get_all_lsas().len(), 1);
+        assert!(table.find_route_from("gateway-a", "gateway-d").is_none());
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TopologyEvent::LsaExpired {
+                node_id: "gateway-c".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_one_way_lsa_link_not_used() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        table.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 1));
+        // gateway-c doesn't list gateway-b back: the b->c link is a phantom
+        table.process_lsa(lsa("gateway-c", &["gateway-d"], 1));
+
+        assert_eq!(table.find_route_from("gateway-a", "gateway-c"), None);
+        // Our own connected peers don't need to be confirmed
+        assert_eq!(
+            table.find_route_from("gateway-a", "gateway-b"),
+            Some(vec!["gateway-b".to_string()])
+        );
+
+        table.process_lsa(lsa("gateway-c", &["gateway-b", "gateway-d"], 2));
+        assert_eq!(
+            table.find_route_from("gateway-a", "gateway-c"),
+            Some(vec!["gateway-b".to_string(), "gateway-c".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_lsa_supersedes_previous() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+
+        let regular = table.generate_lsa("gateway-a");
+        let withdrawal = table.generate_withdrawal_lsa("gateway-a");
+        assert_eq!(regular.neighbors, vec!["gateway-b"]);
+        assert!(withdrawal.neighbors.is_empty());
+        assert!(withdrawal.sequence > regular.sequence);
+
+        let peer_view = RoutingTable::new();
+        peer_view.process_lsa(regular);
+        assert!(peer_view.process_lsa(withdrawal));
+        assert!(peer_view.get_all_lsas()[0].neighbors.is_empty());
+    }
+
+    #[test]
+    fn test_fresh_table_absorbs_dumped_lsas() {
+        // gateway-b's view of a linear mesh a - b - c - d
+        let peer_view = RoutingTable::new();
+        peer_view.process_lsa(lsa("gateway-a", &["gateway-b"], 3));
+        peer_view.process_lsa(lsa("gateway-c", &["gateway-b", "gateway-d"], 7));
+        peer_view.process_lsa(lsa("gateway-d", &["gateway-c"], 2));
+
+        // gateway-a just came up knowing only its direct link to gateway-b
+        let fresh = RoutingTable::new();
+        fresh.add_peer(discovered_peer("gateway-b", PeerStatus::Connected));
+        fresh.process_lsa(lsa("gateway-b", &["gateway-a", "gateway-c"], 5));
+        assert_eq!(fresh.find_route_from("gateway-a", "gateway-d"), None);
+
+        let accepted = peer_view.get_all_lsas().into_iter().filter(|l| fresh.process_lsa(l.clone())).count();
+        assert_eq!(accepted, 3);
+        assert_eq!(
+            fresh.find_route_from("gateway-a", "gateway-d"),
+            Some(vec!["gateway-b".to_string(), "gateway-c".to_string(), "gateway-d".to_string()])
+        );
+
+        // Replaying the same dump changes nothing
+        assert!(peer_view.get_all_lsas().into_iter().all(|l| !fresh.process_lsa(l)));
+        let sequences: Vec<u64> = fresh.get_topology_snapshot().nodes.iter().map(|n| n.sequence).collect();
+        assert_eq!(sequences, vec![3, 5, 7, 2]);
+    }
+
+    fn routes(next_hops: &[&str]) -> Vec<Vec<String>> {
+        next_hops.iter().map(|hop| vec![hop.to_string(), "gateway-d".to_string()]).collect()
+    }
+
+    #[test]
+    fn test_round_robin_alternates_next_hops() {
+        let balancer = LoadBalancer::new();
+        let picks: Vec<String> = (0..4)
+            .map(|_| balancer.select(BalanceStrategy::RoundRobin, "gateway-d", routes(&["gateway-b", "gateway-c"])).unwrap()[0].clone())
+            .collect();
+        assert_eq!(picks, vec!["gateway-b", "gateway-c", "gateway-b", "gateway-c"]);
+        assert_eq!(balancer.usage("gateway-b"), 2);
+        assert_eq!(balancer.usage("gateway-c"), 2);
+
+        // Two routes through the same next hop count as one candidate
+        let mut shared = routes(&["gateway-b"]);
+        shared.push(vec!["gateway-b".to_string(), "gateway-e".to_string(), "gateway-d".to_string()]);
+        let route = balancer.select(BalanceStrategy::RoundRobin, "gateway-x", shared).unwrap();
+        assert_eq!(route, vec!["gateway-b", "gateway-d"]);
+        assert_eq!(balancer.select(BalanceStrategy::RoundRobin, "gateway-d", vec![]), None);
+    }
+
+    #[test]
+    fn test_random_stays_within_candidates() {
+        let balancer = LoadBalancer::new();
+        for _ in 0..50 {
+            let route = balancer.select(BalanceStrategy::Random, "gateway-d", routes(&["gateway-b", "gateway-c"])).unwrap();
+            assert!(route[0] == "gateway-b" || route[0] == "gateway-c");
+        }
+        assert_eq!(balancer.usage("gateway-b") + balancer.usage("gateway-c"), 50);
+    }
+
+    #[test]
+    fn test_same_rng_seed_gives_same_random_choices() {
+        let picks = |table: RoutingTable| -> Vec<String> {
+            let balancer = LoadBalancer::with_rng(table.rng().clone());
+            (0..20)
+                .map(|_| balancer.select(BalanceStrategy::Random, "gateway-d", routes(&["gateway-b", "gateway-c", "gateway-e"])).unwrap()[0].clone())
+                .collect()
+        };
+
+        let first = picks(RoutingTable::new().with_rng_seed(42));
+        assert_eq!(first, picks(RoutingTable::from_config("gateway-a", vec![]).with_rng_seed(42)));
+        assert_ne!(first, picks(RoutingTable::new().with_rng_seed(43)));
+        for hop in ["gateway-b", "gateway-c", "gateway-e"] {
+            assert!(first.iter().any(|pick| pick == hop), "{} never picked: {:?}", hop, first);
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_prefers_idle_next_hop() {
+        let balancer = LoadBalancer::new();
+        balancer.select(BalanceStrategy::RoundRobin, "gateway-x", routes(&["gateway-b"]));
+
+        // gateway-c has never been used, then gateway-b is the older one
+        let first = balancer.select(BalanceStrategy::LeastRecentlyUsed, "gateway-d", routes(&["gateway-b", "gateway-c"])).unwrap();
+        let second = balancer.select(BalanceStrategy::LeastRecentlyUsed, "gateway-d", routes(&["gateway-b", "gateway-c"])).unwrap();
+        assert_eq!(first[0], "gateway-c");
+        assert_eq!(second[0], "gateway-b");
+    }
+
+    #[test]
+    fn test_topology_snapshot() {
+        let table = RoutingTable::new();
+        table.add_peer(discovered_peer("gateway-c", PeerStatus::Connected));
+        table.add_peer(discovered_peer("gateway-b", PeerStatus::Unknown));
+        table.process_lsa(lsa("gateway-c", &["gateway-a", "gateway-d"], 4));
+        table.process_lsa(LinkStateAdvertisement {
+            neighbor_costs: BTreeMap::from([("gateway-c".to_string(), 5)]),
+            ..lsa("gateway-b", &["gateway-a", "gateway-c"], 2)
+        });
+
+        let snapshot = table.get_topology_snapshot();
+
+        let nodes: Vec<(&str, u64)> = snapshot.nodes.iter().map(|n| (n.node_id.as_str(), n.sequence)).collect();
+        assert_eq!(nodes, vec![("gateway-b", 2), ("gateway-c", 4)]);
+        assert_eq!(snapshot.nodes[0].neighbors, vec!["gateway-a", "gateway-c"]);
+        assert_eq!(snapshot.nodes[0].neighbor_costs.get("gateway-c"), Some(&5));
+        assert!(snapshot.nodes[1].timestamp.is_some());
+
+        let peers: Vec<&str> = snapshot.peers.iter().map(|p| p.node_id.as_str()).collect();
+        assert_eq!(peers, vec!["gateway-b", "gateway-c"]);
+    }
 }
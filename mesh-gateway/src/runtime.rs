@@ -0,0 +1,581 @@
+use crate::cert_watch::spawn_file_watch_task;
+use crate::config::GatewayConfig;
+use crate::routing::PeerDiff;
+use crate::server::{
+    broadcast_own_lsa, reload_tls_config, withdraw_from_mesh, spawn_convergence_task, spawn_health_check_task, spawn_link_change_task, spawn_lsa_aging_task, spawn_lsa_broadcast_task, spawn_lsa_sync_task, spawn_forward_queue_task, TlsOptions,
+    spawn_peer_pruning_task, spawn_peer_staleness_task, spawn_peer_status_hook_task, spawn_reachability_log_task, spawn_receipt_sweep_task, spawn_seed_discovery_task, AppState,
+};
+use anyhow::Result;
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How long each peer gets to accept our withdrawal LSA during shutdown
+const WITHDRAWAL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What changed when a new configuration was applied
+#[derive(Debug, Default)]
+pub struct ReloadSummary {
+    pub peers: PeerDiff,
+    /// Background tasks that were restarted with new parameters
+    pub restarted_tasks: Vec<&'static str>,
+    /// Changed settings that only take effect after a full restart
+    pub requires_restart: Vec<&'static str>,
+}
+
+/// Owns a running gateway's state and background tasks
+///
+/// Reloading applies a new configuration in place: peers are added and
+/// removed, handlers see the new settings, and only the background tasks
+/// whose parameters changed are restarted. The LSA database and in-flight
+/// forwards are untouched.
+pub struct GatewayRuntime {
+    state: AppState,
+    health_check: JoinHandle<()>,
+    lsa_broadcast: JoinHandle<()>,
+    lsa_aging: JoinHandle<()>,
+    peer_pruning: Option<JoinHandle<()>>,
+    peer_staleness: Option<JoinHandle<()>>,
+    reachability_log: Option<JoinHandle<()>>,
+    peer_status_hook: Option<JoinHandle<()>>,
+    seed_discovery: Option<JoinHandle<()>>,
+    receipt_sweep: JoinHandle<()>,
+    convergence: JoinHandle<()>,
+    link_change: JoinHandle<()>,
+    lsa_sync: JoinHandle<()>,
+    forward_queue: JoinHandle<()>,
+    /// The running server's TLS configuration, when certificates can be reloaded
+    tls: Option<RustlsConfig>,
+    cert_watch: Option<JoinHandle<()>>,
+}
+
+impl GatewayRuntime {
+    /// Spawn the background tasks for a gateway using its current configuration
+    pub fn start(state: AppState) -> Self {
+        let config = state.config();
+
+        Self {
+            health_check: spawn_health_check(&state, &config),
+            lsa_broadcast: spawn_lsa_broadcast(&state, &config),
+            lsa_aging: spawn_lsa_aging(&state, &config),
+            peer_pruning: spawn_peer_pruning(&state, &config),
+            peer_staleness: spawn_peer_staleness(&state, &config),
+            reachability_log: spawn_reachability_log(&state, &config),
+            peer_status_hook: spawn_peer_status_hook(&state, &config),
+            seed_discovery: spawn_seed_discovery(&state, &config),
+            receipt_sweep: spawn_receipt_sweep(&state, &config),
+            convergence: spawn_convergence(&state),
+            link_change: spawn_link_change(&state),
+            lsa_sync: spawn_lsa_sync_task(state.clone(), state.topology_events.subscribe()),
+            forward_queue: spawn_forward_queue_task(state.clone()),
+            tls: None,
+            cert_watch: None,
+            state,
+        }
+    }
+
+    /// Attach the server's TLS configuration so certificates can be reloaded
+    pub fn with_tls(mut self, tls: RustlsConfig) -> Self {
+        self.cert_watch = spawn_cert_watch(&self.state, &self.state.config(), &tls);
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Reload the server certificate and key from the configured paths
+    ///
+    /// The new pair is validated before it is swapped in; if it is
+    /// mismatched or doesn't chain to the CA the current one stays active.
+    pub fn reload_certificates(&self) -> Result<()> {
+        let Some(tls) = &self.tls else {
+            anyhow::bail!("No TLS configuration attached to reload");
+        };
+        reload_certificates(&self.state, tls)
+    }
+
+    /// Re-read the configuration file at `path` and apply it, as on SIGHUP
+    ///
+    /// If the file can't be loaded the current configuration stays in effect.
+    pub fn reload_from_file(&mut self, path: impl AsRef<Path>) -> Result<ReloadSummary> {
+        let new_config = GatewayConfig::from_file(path)?;
+        self.reload(new_config)
+    }
+
+    /// Apply a new configuration without restarting the gateway
+    pub fn reload(&mut self, new_config: GatewayConfig) -> Result<ReloadSummary> {
+        let old = self.state.config();
+        if new_config.node_id != old.node_id {
+            anyhow::bail!(
+                "node_id cannot be changed by a reload ('{}' -> '{}'); restart the gateway instead",
+                old.node_id,
+                new_config.node_id
+            );
+        }
+
+        let mut summary = ReloadSummary {
+            peers: self.state.routing_table.apply_peer_diff(&new_config.peers),
+            ..ReloadSummary::default()
+        };
+        self.state.forwarder.update_peer_protocols(&new_config.peers);
+        self.state.routing_table.set_lsa_flood_scope(new_config.lsa_flood_scope);
+        self.state.routing_table.set_stability_tiebreak(new_config.stability_tiebreak);
+        self.state.routing_table.set_max_route_hops(new_config.max_route_hops);
+        self.state.routing_table.set_require_signed_lsas(new_config.rejects_unsigned_lsas());
+
+        if new_config.health_check_interval_secs != old.health_check_interval_secs
+            || new_config.health_check_timeout_secs != old.health_check_timeout_secs
+        {
+            self.health_check.abort();
+            self.health_check = spawn_health_check(&self.state, &new_config);
+            summary.restarted_tasks.push("health_check");
+        }
+
+        if new_config.lsa_interval_secs != old.lsa_interval_secs {
+            self.lsa_broadcast.abort();
+            self.lsa_broadcast = spawn_lsa_broadcast(&self.state, &new_config);
+            summary.restarted_tasks.push("lsa_broadcast");
+        }
+
+        if new_config.lsa_max_age_secs != old.lsa_max_age_secs {
+            self.lsa_aging.abort();
+            self.lsa_aging = spawn_lsa_aging(&self.state, &new_config);
+            summary.restarted_tasks.push("lsa_aging");
+        }
+
+        if new_config.peer_max_down_secs != old.peer_max_down_secs
+            || new_config.prune_static_peers != old.prune_static_peers
+        {
+            if let Some(task) = self.peer_pruning.take() {
+                task.abort();
+            }
+            self.peer_pruning = spawn_peer_pruning(&self.state, &new_config);
+            summary.restarted_tasks.push("peer_pruning");
+        }
+
+        if new_config.peer_stale_secs != old.peer_stale_secs {
+            if let Some(task) = self.peer_staleness.take() {
+                task.abort();
+            }
+            self.peer_staleness = spawn_peer_staleness(&self.state, &new_config);
+            summary.restarted_tasks.push("peer_staleness");
+        }
+
+        if new_config.reachability_log_secs != old.reachability_log_secs {
+            if let Some(task) = self.reachability_log.take() {
+                task.abort();
+            }
+            self.reachability_log = spawn_reachability_log(&self.state, &new_config);
+            summary.restarted_tasks.push("reachability_log");
+        }
+
+        if new_config.peer_status_command != old.peer_status_command {
+            if let Some(task) = self.peer_status_hook.take() {
+                task.abort();
+            }
+            self.peer_status_hook = spawn_peer_status_hook(&self.state, &new_config);
+            summary.restarted_tasks.push("peer_status_hook");
+        }
+
+        if new_config.seed_peers != old.seed_peers || new_config.seed_discovery_secs != old.seed_discovery_secs {
+            if let Some(task) = self.seed_discovery.take() {
+                task.abort();
+            }
+            self.seed_discovery = spawn_seed_discovery(&self.state, &new_config);
+            summary.restarted_tasks.push("seed_discovery");
+        }
+
+        if new_config.cert_watch_secs != old.cert_watch_secs
+            && let Some(tls) = &self.tls
+        {
+            if let Some(task) = self.cert_watch.take() {
+                task.abort();
+            }
+            self.cert_watch = spawn_cert_watch(&self.state, &new_config, tls);
+            summary.restarted_tasks.push("cert_watch");
+        }
+
+        let restart_only = [
+            ("listen_port", new_config.listen_port != old.listen_port),
+            ("bind_address", new_config.bind_address != old.bind_address),
+            ("additional_listen_addrs", new_config.additional_listen_addrs != old.additional_listen_addrs),
+            ("control_socket", new_config.control_socket != old.control_socket),
+            ("cert_path", new_config.cert_path != old.cert_path),
+            ("key_path", new_config.key_path != old.key_path),
+            ("ca_cert_path", new_config.ca_cert_path != old.ca_cert_path),
+            ("ca_cert_paths", new_config.ca_cert_paths != old.ca_cert_paths),
+            ("peer_name_verification", new_config.peer_name_verification != old.peer_name_verification),
+            ("min_tls_version", new_config.min_tls_version != old.min_tls_version),
+            ("cipher_suites", new_config.cipher_suites != old.cipher_suites),
+            ("http2_keep_alive_interval_secs", new_config.http2_keep_alive_interval_secs != old.http2_keep_alive_interval_secs),
+            ("http2_max_concurrent_streams", new_config.http2_max_concurrent_streams != old.http2_max_concurrent_streams),
+            ("http2_initial_window_size", new_config.http2_initial_window_size != old.http2_initial_window_size),
+            ("sign_lsas", new_config.sign_lsas != old.sign_lsas),
+            ("recipient_certs", new_config.recipient_certs != old.recipient_certs),
+            ("audit_log_path", new_config.audit_log_path != old.audit_log_path),
+            ("audit_log_max_bytes", new_config.audit_log_max_bytes != old.audit_log_max_bytes),
+            ("ordered_forwarding", new_config.ordered_forwarding != old.ordered_forwarding),
+            ("ordered_queue_depth", new_config.ordered_queue_depth != old.ordered_queue_depth),
+            ("wire_encoding", new_config.wire_encoding != old.wire_encoding),
+            ("dscp_classes", new_config.dscp_classes != old.dscp_classes),
+            ("connect_timeout_ms", new_config.connect_timeout_ms != old.connect_timeout_ms),
+            ("request_timeout_ms", new_config.request_timeout_ms != old.request_timeout_ms),
+            ("forward_retries", new_config.forward_retries != old.forward_retries),
+            ("forward_retry_base_ms", new_config.forward_retry_base_ms != old.forward_retry_base_ms),
+            ("max_outbound_requests", new_config.max_outbound_requests != old.max_outbound_requests),
+            ("convergence_quiet_secs", new_config.convergence_quiet_secs != old.convergence_quiet_secs),
+            ("receipt_capacity", new_config.receipt_capacity != old.receipt_capacity),
+            ("receipt_ttl_secs", new_config.receipt_ttl_secs != old.receipt_ttl_secs),
+            ("dedup_capacity", new_config.dedup_capacity != old.dedup_capacity),
+            ("dedup_ttl_secs", new_config.dedup_ttl_secs != old.dedup_ttl_secs),
+            ("max_message_bytes", new_config.max_message_bytes != old.max_message_bytes),
+            ("max_clock_skew_secs", new_config.max_clock_skew_secs != old.max_clock_skew_secs),
+            ("nonce_cache_capacity", new_config.nonce_cache_capacity != old.nonce_cache_capacity),
+            ("log_format", new_config.log_format != old.log_format),
+        ];
+        summary.requires_restart = restart_only
+            .iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(name, _)| *name)
+            .collect();
+
+        self.state.set_config(new_config);
+
+        // A removed peer changes our neighbor set; advertise it right away
+        if !summary.peers.removed.is_empty() {
+            broadcast_own_lsa(&self.state.node_id, &self.state.routing_table, &self.state.http_client, self.state.forwarder.wire_encoding(), &self.state.outbound);
+        }
+
+        tracing::info!(
+            "Configuration reloaded: peers +{:?} -{:?} ~{:?}, restarted tasks {:?}",
+            summary.peers.added,
+            summary.peers.removed,
+            summary.peers.updated,
+            summary.restarted_tasks
+        );
+        if !summary.requires_restart.is_empty() {
+            tracing::warn!(
+                "Changed settings only take effect after a restart: {:?}",
+                summary.requires_restart
+            );
+        }
+
+        Ok(summary)
+    }
+
+    /// Stop the background tasks and, if configured, withdraw from the mesh
+    ///
+    /// Tasks are stopped first so a periodic broadcast can't re-advertise
+    /// our neighbors after the withdrawal LSA has gone out.
+    pub async fn shutdown(self) {
+        self.stop_tasks();
+
+        let config = self.state.config();
+        if config.withdraw_on_shutdown {
+            withdraw_from_mesh(
+                &self.state.node_id,
+                &self.state.routing_table,
+                &self.state.http_client,
+                self.state.forwarder.wire_encoding(),
+                &self.state.outbound,
+                WITHDRAWAL_TIMEOUT,
+            )
+            .await;
+        }
+    }
+
+    fn stop_tasks(&self) {
+        self.health_check.abort();
+        self.lsa_broadcast.abort();
+        self.lsa_aging.abort();
+        self.receipt_sweep.abort();
+        self.convergence.abort();
+        self.link_change.abort();
+        self.lsa_sync.abort();
+        self.forward_queue.abort();
+        if let Some(task) = &self.peer_pruning {
+            task.abort();
+        }
+        if let Some(task) = &self.peer_staleness {
+            task.abort();
+        }
+        if let Some(task) = &self.reachability_log {
+            task.abort();
+        }
+        if let Some(task) = &self.peer_status_hook {
+            task.abort();
+        }
+        if let Some(task) = &self.seed_discovery {
+            task.abort();
+        }
+        if let Some(task) = &self.cert_watch {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for GatewayRuntime {
+    fn drop(&mut self) {
+        self.stop_tasks();
+    }
+}
+
+fn spawn_health_check(state: &AppState, config: &GatewayConfig) -> JoinHandle<()> {
+    spawn_health_check_task(
+        state.routing_table.clone(),
+        state.http_client.clone(),
+        Duration::from_secs(config.health_check_interval_secs),
+        Duration::from_secs(config.health_check_timeout_secs),
+    )
+}
+
+fn spawn_lsa_broadcast(state: &AppState, config: &GatewayConfig) -> JoinHandle<()> {
+    spawn_lsa_broadcast_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        state.http_client.clone(),
+        state.forwarder.wire_encoding(),
+        state.outbound.clone(),
+        Duration::from_secs(config.lsa_interval_secs),
+    )
+}
+
+fn spawn_lsa_aging(state: &AppState, config: &GatewayConfig) -> JoinHandle<()> {
+    spawn_lsa_aging_task(
+        state.routing_table.clone(),
+        Duration::from_secs(config.lsa_max_age_secs),
+    )
+}
+
+fn spawn_peer_pruning(state: &AppState, config: &GatewayConfig) -> Option<JoinHandle<()>> {
+    let max_down_secs = config.peer_max_down_secs?;
+    Some(spawn_peer_pruning_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        state.http_client.clone(),
+        state.forwarder.wire_encoding(),
+        state.outbound.clone(),
+        Duration::from_secs(max_down_secs),
+        config.prune_static_peers,
+    ))
+}
+
+fn spawn_peer_staleness(state: &AppState, config: &GatewayConfig) -> Option<JoinHandle<()>> {
+    let stale_secs = config.peer_stale_secs?;
+    Some(spawn_peer_staleness_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        state.http_client.clone(),
+        state.forwarder.wire_encoding(),
+        state.outbound.clone(),
+        Duration::from_secs(stale_secs),
+    ))
+}
+
+fn spawn_reachability_log(state: &AppState, config: &GatewayConfig) -> Option<JoinHandle<()>> {
+    let period_secs = config.reachability_log_secs?;
+    Some(spawn_reachability_log_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        Duration::from_secs(period_secs.max(1)),
+    ))
+}
+
+fn spawn_seed_discovery(state: &AppState, config: &GatewayConfig) -> Option<JoinHandle<()>> {
+    if config.seed_peers.is_empty() {
+        return None;
+    }
+    Some(spawn_seed_discovery_task(
+        state.clone(),
+        config.seed_peers.clone(),
+        Duration::from_secs(config.seed_discovery_secs.max(1)),
+    ))
+}
+
+fn spawn_peer_status_hook(state: &AppState, config: &GatewayConfig) -> Option<JoinHandle<()>> {
+    let command = config.peer_status_command.clone()?;
+    Some(spawn_peer_status_hook_task(
+        state.node_id.clone(),
+        command,
+        state.topology_events.subscribe(),
+    ))
+}
+
+fn spawn_cert_watch(state: &AppState, config: &GatewayConfig, tls: &RustlsConfig) -> Option<JoinHandle<()>> {
+    let period_secs = config.cert_watch_secs?;
+    let mut paths = vec![PathBuf::from(&config.cert_path), PathBuf::from(&config.key_path)];
+    paths.extend(config.trusted_ca_paths().into_iter().map(PathBuf::from));
+
+    let state = state.clone();
+    let tls = tls.clone();
+    Some(spawn_file_watch_task(paths, Duration::from_secs(period_secs.max(1)), move || {
+        tracing::info!("🔐 Certificate files changed, reloading");
+        // Failures are logged; the current certificate keeps serving
+        let _ = reload_certificates(&state, &tls);
+    }))
+}
+
+/// Reload the server certificate and key, logging the outcome
+fn reload_certificates(state: &AppState, tls: &RustlsConfig) -> Result<()> {
+    let config = state.config();
+    let options = TlsOptions::from_config(&config)?;
+    match reload_tls_config(tls, &config.cert_path, &config.key_path, &config.trusted_ca_paths(), &options) {
+        Ok(()) => {
+            tracing::info!("🔐 Reloaded TLS certificate from {}", config.cert_path);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Certificate reload rejected, keeping current certificate: {:#}", e);
+            Err(e)
+        }
+    }
+}
+
+fn spawn_receipt_sweep(state: &AppState, config: &GatewayConfig) -> JoinHandle<()> {
+    // Sweep a few times per TTL, but no more than once a second or less than once a minute
+    let period = Duration::from_secs(config.receipt_ttl_secs / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    spawn_receipt_sweep_task(state.receipts.clone(), period)
+}
+
+fn spawn_convergence(state: &AppState) -> JoinHandle<()> {
+    spawn_convergence_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        state.convergence.clone(),
+        state.topology_events.subscribe(),
+    )
+}
+
+fn spawn_link_change(state: &AppState) -> JoinHandle<()> {
+    spawn_link_change_task(
+        state.node_id.clone(),
+        state.routing_table.clone(),
+        state.http_client.clone(),
+        state.forwarder.wire_encoding(),
+        state.outbound.clone(),
+        state.topology_events.subscribe(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PeerConfig, PeerProtocol};
+    use crate::routing::RoutingTable;
+    use crate::types::LinkStateAdvertisement;
+    use std::collections::BTreeMap;
+    use std::time::SystemTime;
+
+    fn peer(node_id: &str, address: &str) -> PeerConfig {
+        PeerConfig {
+            node_id: node_id.to_string(),
+            address: address.to_string(),
+            protocol: PeerProtocol::default(),
+            cost: 1,
+        }
+    }
+
+    fn config(peers: Vec<PeerConfig>) -> GatewayConfig {
+        GatewayConfig {
+            node_id: "gateway-a".to_string(),
+            peers,
+            ..GatewayConfig::default()
+        }
+    }
+
+    fn start(config: &GatewayConfig, routing_table: &RoutingTable) -> GatewayRuntime {
+        let state = AppState::from_config(config.clone(), routing_table.clone(), reqwest::Client::new());
+        GatewayRuntime::start(state)
+    }
+
+    #[tokio::test]
+    async fn test_reload_changes_lsa_interval_and_keeps_topology() {
+        let initial = config(vec![peer("gateway-b", "127.0.0.1:8002")]);
+        let routing_table = RoutingTable::from_config(&initial.node_id, initial.peers.clone());
+        routing_table.process_lsa(LinkStateAdvertisement {
+            node_id: "gateway-c".to_string(),
+            neighbors: vec!["gateway-b".to_string()],
+            neighbor_costs: BTreeMap::new(),
+            sequence: 1,
+            epoch: 1,
+            timestamp: Some(SystemTime::now()),
+            flood_ttl: None,
+            signature: None,
+            signer_cert: None,
+            stability_hint: None,
+        });
+        let mut runtime = start(&initial, &routing_table);
+
+        let reloaded = GatewayConfig {
+            lsa_interval_secs: 5,
+            peers: vec![peer("gateway-b", "127.0.0.1:8002"), peer("gateway-d", "127.0.0.1:8004")],
+            ..initial.clone()
+        };
+        let summary = runtime.reload(reloaded).unwrap();
+
+        assert_eq!(summary.restarted_tasks, vec!["lsa_broadcast"]);
+        assert_eq!(summary.peers.added, vec!["gateway-d"]);
+        assert!(summary.requires_restart.is_empty());
+        assert_eq!(runtime.state().config().lsa_interval_secs, 5);
+
+        // Learned topology survives the reload
+        assert_eq!(routing_table.get_all_lsas().len(), 1);
+        assert_eq!(routing_table.peer_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_file_diffs_peers() {
+        let dir = std::env::temp_dir().join(format!("mesh-gateway-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gateway.toml");
+        let write_config = |listen_port: u16, cert_path: &str, peer_name_verification: &str, peers: &[(&str, &str)]| {
+            let mut contents = format!(
+                "node_id = \"gateway-a\"\nlisten_port = {}\ncert_path = \"{}\"\npeer_name_verification = \"{}\"\n",
+                listen_port, cert_path, peer_name_verification
+            );
+            for (node_id, address) in peers {
+                contents.push_str(&format!("[[peers]]\nnode_id = \"{}\"\naddress = \"{}\"\n", node_id, address));
+            }
+            std::fs::write(&path, contents).unwrap();
+        };
+
+        write_config(8001, "certs/gateway-a.crt", "strict", &[("gateway-b", "127.0.0.1:8002"), ("gateway-c", "127.0.0.1:8003")]);
+        let initial = GatewayConfig::from_file(&path).unwrap();
+        let routing_table = RoutingTable::from_config(&initial.node_id, initial.peers.clone());
+        routing_table.update_peer_status("gateway-b", crate::types::PeerStatus::Connected);
+        let mut runtime = start(&initial, &routing_table);
+
+        write_config(9001, "certs/rotated.crt", "permissive", &[("gateway-b", "127.0.0.1:8002"), ("gateway-d", "127.0.0.1:8004")]);
+        let summary = runtime.reload_from_file(&path).unwrap();
+
+        assert_eq!(summary.peers.added, vec!["gateway-d"]);
+        assert_eq!(summary.peers.removed, vec!["gateway-c"]);
+        assert!(summary.peers.updated.is_empty());
+        // The unchanged peer keeps its status
+        assert_eq!(routing_table.get_peer("gateway-b").unwrap().status, crate::types::PeerStatus::Connected);
+        assert_eq!(summary.requires_restart, vec!["listen_port", "cert_path", "peer_name_verification"]);
+
+        // A file that no longer parses leaves the running configuration alone
+        std::fs::write(&path, "node_id = ").unwrap();
+        assert!(runtime.reload_from_file(&path).is_err());
+        assert_eq!(runtime.state().config().peers.len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_node_id_change() {
+        let initial = config(vec![]);
+        let mut runtime = start(&initial, &RoutingTable::new());
+
+        let renamed = GatewayConfig {
+            node_id: "gateway-z".to_string(),
+            ..initial
+        };
+        assert!(runtime.reload(renamed).is_err());
+        assert_eq!(runtime.state().config().node_id, "gateway-a");
+    }
+}
@@ -0,0 +1,210 @@
+use crate::certs::{load_ca_certs, load_cert, load_private_key};
+use crate::types::LinkStateAdvertisement;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_ASN1_SIGNING};
+use rustls::server::{AllowAnyAuthenticatedClient, ClientCertVerifier};
+use rustls::{Certificate, RootCertStore};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Bytes covered by an LSA signature
+///
+/// Everything the originator asserts is signed; the signature fields
+/// themselves are cleared before serializing, as is `flood_ttl` since
+/// every re-flooding hop legitimately decrements it.
+pub fn signing_payload(lsa: &LinkStateAdvertisement) -> Vec<u8> {
+    let mut unsigned = lsa.clone();
+    unsigned.signature = None;
+    unsigned.signer_cert = None;
+    unsigned.flood_ttl = None;
+    serde_json::to_vec(&unsigned).expect("LSA serialization cannot fail")
+}
+
+/// Signs this node's LSAs with the private key belonging to its certificate
+pub struct LsaSigner {
+    key_pair: EcdsaKeyPair,
+    cert_der: Vec<u8>,
+    rng: SystemRandom,
+}
+
+impl LsaSigner {
+    /// Load the signing key and certificate (ECDSA P-256, as issued by gen_certs)
+    pub fn from_files(cert_path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> Result<Self> {
+        let cert = load_cert(&cert_path)?.remove(0);
+        let key = load_private_key(&key_path)?;
+
+        let rng = SystemRandom::new();
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &key.0, &rng)
+            .map_err(|e| anyhow::anyhow!("Unsupported LSA signing key {:?}: {}", key_path.as_ref(), e))?;
+
+        Ok(Self {
+            key_pair,
+            cert_der: cert.0,
+            rng,
+        })
+    }
+
+    /// Sign an LSA in place, attaching the signature and our certificate
+    pub fn sign(&self, lsa: &mut LinkStateAdvertisement) -> Result<()> {
+        let signature = self
+            .key_pair
+            .sign(&self.rng, &signing_payload(lsa))
+            .map_err(|_| anyhow::anyhow!("Failed to sign LSA"))?;
+
+        lsa.signature = Some(BASE64.encode(signature.as_ref()));
+        lsa.signer_cert = Some(BASE64.encode(&self.cert_der));
+        Ok(())
+    }
+}
+
+/// Verifies LSA signatures against certificates issued by the mesh CA
+#[derive(Clone)]
+pub struct LsaVerifier {
+    cert_verifier: Arc<dyn ClientCertVerifier>,
+}
+
+impl LsaVerifier {
+    /// Create a verifier trusting the given CA certificates
+    pub fn new(roots: RootCertStore) -> Self {
+        Self {
+            cert_verifier: AllowAnyAuthenticatedClient::new(roots).boxed(),
+        }
+    }
+
+    /// Create a verifier trusting the CA certificates in the given PEM files
+    pub fn from_ca_files(ca_cert_paths: &[impl AsRef<Path>]) -> Result<Self> {
+        Ok(Self::new(load_ca_certs(ca_cert_paths)?))
+    }
+
+    /// Verify that an LSA was signed by its originator
+    ///
+    /// The attached certificate must chain to the CA and name the originating
+    /// node as its CommonName, and the signature must match the LSA contents.
+    pub fn verify(&self, lsa: &LinkStateAdvertisement) -> Result<()> {
+        let (Some(signature), Some(signer_cert)) = (&lsa.signature, &lsa.signer_cert) else {
+            anyhow::bail!("LSA from {} is not signed", lsa.node_id);
+        };
+
+        let signature = BASE64.decode(signature).context("Malformed LSA signature")?;
+        let cert_der = BASE64.decode(signer_cert).context("Malformed LSA signer certificate")?;
+
+        self.cert_verifier
+            .verify_client_cert(&Certificate(cert_der.clone()), &[], SystemTime::now())
+            .map_err(|e| anyhow::anyhow!("LSA signer certificate not trusted: {}", e))?;
+
+        let (_, cert) = X509Certificate::from_der(&cert_der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse LSA signer certificate: {}", e))?;
+
+        let common_name = cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .unwrap_or_default();
+
+        if common_name != lsa.node_id {
+            anyhow::bail!(
+                "LSA from {} is signed by a certificate for '{}'",
+                lsa.node_id,
+                common_name
+            );
+        }
+
+        let public_key = UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, cert.public_key().subject_public_key.data.as_ref());
+        public_key
+            .verify(&signing_payload(lsa), &signature)
+            .map_err(|_| anyhow::anyhow!("Invalid signature on LSA from {}", lsa.node_id))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::test_util::TestPki;
+
+    fn lsa(node_id: &str, neighbors: &[&str]) -> LinkStateAdvertisement {
+        LinkStateAdvertisement {
+            node_id: node_id.to_string(),
+            neighbors: neighbors.iter().map(|n| n.to_string()).collect(),
+            neighbor_costs: BTreeMap::new(),
+            sequence: 1,
+            epoch: 1,
+            timestamp: Some(SystemTime::now()),
+            flood_ttl: None,
+            signature: None,
+            signer_cert: None,
+            stability_hint: None,
+        }
+    }
+
+    fn signer(pki: &TestPki, node: &str) -> LsaSigner {
+        LsaSigner::from_files(pki.cert_path(node), pki.key_path(node)).unwrap()
+    }
+
+    #[test]
+    fn test_signed_lsa_verifies_after_transit() {
+        let pki = TestPki::generate(&["gateway-b"]);
+        let verifier = LsaVerifier::from_ca_files(&[pki.ca_path()]).unwrap();
+
+        let mut lsa = lsa("gateway-b", &["gateway-a", "gateway-c"]);
+        signer(&pki, "gateway-b").sign(&mut lsa).unwrap();
+
+        // Re-serialize as a transit hop would before flooding
+        let mut relayed: LinkStateAdvertisement =
+            serde_json::from_str(&serde_json::to_string(&lsa).unwrap()).unwrap();
+        relayed.flood_ttl = Some(2);
+
+        assert!(verifier.verify(&relayed).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_neighbors_rejected() {
+        let pki = TestPki::generate(&["gateway-b"]);
+        let verifier = LsaVerifier::from_ca_files(&[pki.ca_path()]).unwrap();
+
+        let mut lsa = lsa("gateway-b", &["gateway-a"]);
+        signer(&pki, "gateway-b").sign(&mut lsa).unwrap();
+
+        lsa.neighbors.push("gateway-evil".to_string());
+        assert!(verifier.verify(&lsa).is_err());
+    }
+
+    #[test]
+    fn test_lsa_signed_by_other_node_rejected() {
+        let pki = TestPki::generate(&["gateway-b", "gateway-c"]);
+        let verifier = LsaVerifier::from_ca_files(&[pki.ca_path()]).unwrap();
+
+        // gateway-c forges an LSA on behalf of gateway-b
+        let mut forged = lsa("gateway-b", &["gateway-c"]);
+        signer(&pki, "gateway-c").sign(&mut forged).unwrap();
+
+        let err = verifier.verify(&forged).unwrap_err();
+        assert!(err.to_string().contains("gateway-c"));
+    }
+
+    #[test]
+    fn test_signer_from_untrusted_ca_rejected() {
+        let trusted = TestPki::generate(&[]);
+        let rogue = TestPki::generate(&["gateway-b"]);
+        let verifier = LsaVerifier::from_ca_files(&[trusted.ca_path()]).unwrap();
+
+        let mut lsa = lsa("gateway-b", &["gateway-a"]);
+        signer(&rogue, "gateway-b").sign(&mut lsa).unwrap();
+
+        assert!(verifier.verify(&lsa).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_lsa_fails_verification() {
+        let pki = TestPki::generate(&[]);
+        let verifier = LsaVerifier::from_ca_files(&[pki.ca_path()]).unwrap();
+        assert!(verifier.verify(&lsa("gateway-b", &["gateway-a"])).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// Shared helpers for tests that need real mTLS endpoints.
+// Generates a throwaway CA and node certificates in a temp directory so tests
+// don't depend on the (gitignored) keys under certs/.
+
+use crate::client::{create_mtls_client, ClientTimeouts};
+use crate::config::PeerNameVerification;
+use crate::server::{build_tls_config, PeerIdentityAcceptor, TlsOptions};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rcgen::{CertificateParams, DistinguishedName, DnType, PKCS_ECDSA_P256_SHA256};
+use reqwest::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_DIR: AtomicU64 = AtomicU64::new(0);
+
+/// A temporary PKI: one CA plus a cert/key pair per node
+pub struct TestPki {
+    dir: PathBuf,
+}
+
+impl TestPki {
+    /// Generate a CA and certificates for each of the given node ids
+    pub fn generate(nodes: &[&str]) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "mesh-gateway-test-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ca = generate_ca("Test Root CA");
+        fs::write(dir.join("ca.crt"), ca.serialize_pem().unwrap()).unwrap();
+        fs::write(dir.join("ca.key"), ca.serialize_private_key_pem()).unwrap();
+
+        for node in nodes {
+            let cert = generate_node_cert(node);
+            fs::write(dir.join(format!("{}.crt", node)), cert.serialize_pem_with_signer(&ca).unwrap()).unwrap();
+            fs::write(dir.join(format!("{}.key", node)), cert.serialize_private_key_pem()).unwrap();
+        }
+
+        Self { dir }
+    }
+
+    /// Like `generate`, but the node certificates are issued by an
+    /// intermediate CA (see `intermediate_path`); each node's .crt holds
+    /// only its own certificate
+    pub fn generate_with_intermediate(nodes: &[&str]) -> Self {
+        let pki = Self::generate(&[]);
+        let ca_key = rcgen::KeyPair::from_pem(&fs::read_to_string(pki.dir.join("ca.key")).unwrap()).unwrap();
+        let ca_params = CertificateParams::from_ca_cert_pem(&fs::read_to_string(pki.ca_path()).unwrap(), ca_key).unwrap();
+        let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+
+        let intermediate = generate_ca("Test Intermediate CA");
+        fs::write(pki.intermediate_path(), intermediate.serialize_pem_with_signer(&ca).unwrap()).unwrap();
+
+        for node in nodes {
+            let cert = generate_node_cert(node);
+            fs::write(pki.cert_path(node), cert.serialize_pem_with_signer(&intermediate).unwrap()).unwrap();
+            fs::write(pki.key_path(node), cert.serialize_private_key_pem()).unwrap();
+        }
+
+        pki
+    }
+
+    pub fn intermediate_path(&self) -> PathBuf {
+        self.dir.join("intermediate.crt")
+    }
+
+    pub fn cert_path(&self, node: &str) -> PathBuf {
+        self.dir.join(format!("{}.crt", node))
+    }
+
+    pub fn key_path(&self, node: &str) -> PathBuf {
+        self.dir.join(format!("{}.key", node))
+    }
+
+    pub fn ca_path(&self) -> PathBuf {
+        self.dir.join("ca.crt")
+    }
+
+    /// Create an mTLS client presenting the given node's certificate
+    pub fn client(&self, node: &str) -> Client {
+        create_mtls_client(self.cert_path(node), self.key_path(node), &[self.ca_path()], ClientTimeouts::default(), PeerNameVerification::Strict).unwrap()
+    }
+
+    /// Serve `app` over mTLS as the given node on an ephemeral loopback port
+    pub async fn serve(&self, node: &str, app: Router) -> SocketAddr {
+        self.serve_with_alpn(node, app, vec![]).await.0
+    }
+
+    /// Like `serve`, but also offers HTTP/2 via ALPN
+    pub async fn serve_h2(&self, node: &str, app: Router) -> SocketAddr {
+        self.serve_with_alpn(node, app, vec![b"h2".to_vec(), b"http/1.1".to_vec()]).await.0
+    }
+
+    /// Like `serve`, also returning the TLS config so tests can reload it
+    pub async fn serve_reloadable(&self, node: &str, app: Router) -> (SocketAddr, RustlsConfig) {
+        self.serve_with_alpn(node, app, vec![]).await
+    }
+
+    async fn serve_with_alpn(&self, node: &str, app: Router, alpn: Vec<Vec<u8>>) -> (SocketAddr, RustlsConfig) {
+        let mut config = build_tls_config(self.cert_path(node), self.key_path(node), &[self.ca_path()], &TlsOptions::default()).unwrap();
+        config.alpn_protocols = alpn;
+        let tls = RustlsConfig::from_config(Arc::new(config));
+        let handle = axum_server::Handle::new();
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+        let server = axum_server::bind(addr)
+            .acceptor(PeerIdentityAcceptor::new(tls.clone()))
+            .handle(handle.clone())
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+
+        (handle.listening().await.expect("test server failed to start"), tls)
+    }
+}
+
+impl Drop for TestPki {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn generate_ca(common_name: &str) -> rcgen::Certificate {
+    let mut params = CertificateParams::default();
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    params.key_usages = vec![
+        rcgen::KeyUsagePurpose::KeyCertSign,
+        rcgen::KeyUsagePurpose::CrlSign,
+    ];
+
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, common_name);
+    params.distinguished_name = dn;
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+
+    rcgen::Certificate::from_params(params).unwrap()
+}
+
+fn generate_node_cert(node: &str) -> rcgen::Certificate {
+    let mut params = CertificateParams::default();
+
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, node);
+    params.distinguished_name = dn;
+
+    params.subject_alt_names = vec![
+        rcgen::SanType::DnsName(node.to_string()),
+        rcgen::SanType::DnsName("localhost".to_string()),
+        rcgen::SanType::IpAddress(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)),
+    ];
+    params.extended_key_usages = vec![
+        rcgen::ExtendedKeyUsagePurpose::ServerAuth,
+        rcgen::ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+
+    rcgen::Certificate::from_params(params).unwrap()
+}
@@ -1,4 +1,6 @@
+use crate::compression::Codec;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Custom serializer for SystemTime to RFC3339/ISO 8601 format
@@ -50,6 +52,13 @@ pub struct PeerInfo {
     pub status: PeerStatus,
     #[serde(with = "systemtime_serialization")]
     pub last_seen: Option<SystemTime>,
+    /// Seconds since `last_seen`, filled in when peers are listed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seconds_since_seen: Option<u64>,
+    /// Rolling health-check score from 0.0 (failing) to 1.0 (healthy), once
+    /// the peer has been health-checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_score: Option<f64>,
 }
 
 /// Status of a peer connection
@@ -57,24 +66,182 @@ pub struct PeerInfo {
 #[serde(rename_all = "lowercase")]
 pub enum PeerStatus {
     Connected,
+    /// Reachable, but its recent health checks were slow or flaky
+    Degraded,
     Disconnected,
     Unknown,
 }
 
+impl PeerStatus {
+    /// The status as it appears in JSON responses
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PeerStatus::Connected => "connected",
+            PeerStatus::Degraded => "degraded",
+            PeerStatus::Disconnected => "disconnected",
+            PeerStatus::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the link to the peer can carry traffic (Connected or Degraded)
+    pub fn is_up(&self) -> bool {
+        matches!(self, PeerStatus::Connected | PeerStatus::Degraded)
+    }
+}
+
 /// Request to send a message to another gateway
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendMessageRequest {
     pub to: String,
     pub content: String,
+    /// Maximum number of hops the message may be forwarded
+    /// (the gateway's `message_ttl` when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    /// Higher values are forwarded first when `queued_forwarding` is on (default 0)
+    #[serde(default)]
+    pub priority: u8,
+    /// Force the message through these hops in order instead of the computed
+    /// route; the destination is appended if it isn't listed last
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explicit_route: Option<Vec<String>>,
+    /// `content` is base64 of binary data, delivered as raw bytes
+    #[serde(default)]
+    pub binary: bool,
+}
+
+/// Hop limit assumed for forwarded messages from peers that don't send one
+pub const DEFAULT_MESSAGE_TTL: u8 = 16;
+
+/// Header carrying a message's correlation id from hop to hop, and echoed
+/// on the response
+pub const CORRELATION_ID_HEADER: &str = "x-mesh-correlation-id";
+
+fn default_message_ttl() -> u8 {
+    DEFAULT_MESSAGE_TTL
 }
 
 /// Request to receive a forwarded message from another gateway
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReceiveMessageRequest {
     pub from: String,
     pub to: String,
     pub content: String,
     pub route: Vec<String>,
+    /// Remaining hops this message may be forwarded; a relay that receives
+    /// it at zero drops it as `ttl_exceeded`
+    #[serde(default = "default_message_ttl")]
+    pub ttl: u8,
+    /// Id assigned by the originating node (UUID); the destination acks it
+    /// (empty from peers that predate message ids)
+    #[serde(default)]
+    pub message_id: String,
+    /// When the sending hop sent this copy; a copy outside the receiver's
+    /// clock-skew window is rejected as `replay_rejected`
+    #[serde(default, with = "systemtime_serialization")]
+    pub timestamp: Option<SystemTime>,
+    /// Random value (UUID) chosen by the sending hop for this copy; a nonce
+    /// the receiver has already seen is rejected as `replay_rejected`
+    #[serde(default)]
+    pub nonce: String,
+    /// Set by the origin; with `queued_forwarding` each hop forwards higher
+    /// priorities first
+    #[serde(default)]
+    pub priority: u8,
+    /// `content` is encrypted to the destination's public key (see
+    /// `encrypt_messages`); only the destination can read it
+    #[serde(default)]
+    pub encrypted: bool,
+    /// `content` is base64 of binary data (after decryption, if encrypted)
+    #[serde(default)]
+    pub binary: bool,
+    /// `content` is base64 of the original content compressed with this
+    /// codec (after decryption, if encrypted); only the destination
+    /// decompresses it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Codec>,
+    /// Hops still to visit when the origin forced the path, starting with
+    /// the node the message is sent to; each hop removes itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explicit_route: Option<Vec<String>>,
+    /// Correlation id of the request being handled when the message was
+    /// sent on; travels in the `X-Mesh-Correlation-Id` header, not the body
+    #[serde(skip)]
+    pub correlation_id: Option<String>,
+}
+
+/// Delivery confirmation routed from a message's destination back to its origin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckRequest {
+    pub message_id: String,
+    /// Node that originated the message and is waiting for this ack
+    pub origin: String,
+    /// Destination that received the message
+    pub from: String,
+    /// Route the message took, ending at the destination
+    pub route: Vec<String>,
+    /// Remaining hops this ack may be relayed
+    pub ttl: u8,
+}
+
+/// A message this gateway gave up on, reported to the configured dead-letter sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub from: String,
+    pub to: String,
+    pub content: String,
+    /// Route the message had taken when it was dropped (ending at `dropped_by`)
+    pub route: Vec<String>,
+    /// Terminal status that caused the drop (e.g. "no_route", "failed")
+    pub reason: String,
+    /// Node that made the drop decision
+    pub dropped_by: String,
+    #[serde(with = "systemtime_serialization")]
+    pub timestamp: Option<SystemTime>,
+}
+
+/// A message delivered to this node, as published to local subscribers
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveredMessage {
+    pub message_id: String,
+    /// The message's origin
+    pub from: String,
+    pub payload: Payload,
+}
+
+/// Content of a delivered message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// What a gateway did with a message, as recorded in its audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// Sent from this node; recorded again whenever its receipt changes
+    Originated,
+    /// Relayed by this node toward the destination
+    Forwarded,
+    /// Delivered to this node as the destination
+    Delivered,
+}
+
+/// One line of the audit log (`audit_log_path`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    #[serde(with = "systemtime_serialization")]
+    pub timestamp: Option<SystemTime>,
+    pub event: AuditEvent,
+    pub message_id: String,
+    /// The message's origin
+    pub from: String,
+    pub to: String,
+    pub route: Vec<String>,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 /// Response after sending a message
@@ -82,6 +249,97 @@ pub struct ReceiveMessageRequest {
 pub struct SendMessageResponse {
     pub status: String,
     pub route: Vec<String>,
+    /// Id to look up the message's receipt on the originating node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    /// Why the message failed, when `status` alone doesn't say
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetail>,
+}
+
+/// Request to send a copy of a message to every reachable gateway
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendBroadcastRequest {
+    pub content: String,
+    /// Hop limit for each copy (the gateway's `message_ttl` when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    #[serde(default)]
+    pub priority: u8,
+}
+
+/// Response after broadcasting a message
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastResponse {
+    /// Number of destinations that reported delivery
+    pub delivered: usize,
+    /// Outcome for each destination, as /message/send reports it
+    pub results: BTreeMap<String, SendMessageResponse>,
+}
+
+/// Details of a failed send or forward
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub kind: ErrorKind,
+    /// Human-readable description, e.g. the underlying connection error
+    pub message: String,
+}
+
+/// Category of a send or forward failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The next hop couldn't be reached
+    Connection,
+    /// The next hop didn't answer in time
+    Timeout,
+    /// The next hop answered with an HTTP error
+    NextHopStatus,
+    /// The next hop's answer couldn't be parsed
+    InvalidResponse,
+    /// The destination is known but there is no usable path to it
+    NoRoute,
+    /// The destination isn't known anywhere in the mesh
+    UnknownDestination,
+    /// The message content is larger than `max_message_bytes`
+    PayloadTooLarge,
+    /// The sender isn't the node its client certificate identifies
+    Unauthorized,
+    /// The message is stale or its nonce was already used
+    Replay,
+    /// The content couldn't be encrypted for the destination, or decrypted by it
+    Encryption,
+    /// A hop of the message's `explicit_route` isn't a connected peer of the
+    /// node before it
+    InvalidRoute,
+    /// The request itself is malformed, e.g. `binary` content that isn't
+    /// base64 or a raw send without `X-Mesh-To`
+    InvalidRequest,
+    /// Forwards to the next hop kept failing, so its circuit breaker is
+    /// failing them fast without contacting it
+    CircuitOpen,
+    /// The next hop's send queue (ordered forwarding) is full
+    QueueFull,
+}
+
+impl ErrorDetail {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// Final status of a message this node originated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReceipt {
+    pub message_id: String,
+    pub to: String,
+    pub status: String,
+    pub route: Vec<String>,
+    #[serde(with = "systemtime_serialization")]
+    pub timestamp: Option<SystemTime>,
 }
 
 /// Information about this gateway node
@@ -99,12 +357,34 @@ pub struct PeersResponse {
     pub peers: Vec<PeerInfo>,
 }
 
+/// Peer to add at runtime via `POST /admin/peers`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddPeerRequest {
+    pub node_id: String,
+    /// host:port of the peer's mTLS listener
+    pub address: String,
+}
+
 /// Health check response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
     pub node_id: String,
     pub uptime_seconds: u64,
+    /// Boot epoch of the responding node (changes on every restart)
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+/// Readiness probe response (`/health/ready`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    /// "ready" or "not_ready"
+    pub status: String,
+    pub node_id: String,
+    pub connected_peers: usize,
+    /// Whether an LSA from another node has been learned
+    pub routing_initialized: bool,
 }
 
 /// Link State Advertisement - shares topology information with peers
@@ -116,12 +396,39 @@ pub struct LinkStateAdvertisement {
     /// List of direct peers (neighbors) this node can reach
     pub neighbors: Vec<String>,
 
+    /// Link cost to neighbors whose cost isn't the default of 1
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub neighbor_costs: BTreeMap<String, u32>,
+
     /// Sequence number to detect newer LSAs (higher is newer)
     pub sequence: u64,
 
+    /// Boot epoch of the originator (milliseconds since UNIX epoch at startup)
+    /// A different epoch means the node restarted and its sequence numbers reset
+    #[serde(default)]
+    pub epoch: u64,
+
     /// Timestamp when this LSA was created
     #[serde(with = "systemtime_serialization")]
     pub timestamp: Option<SystemTime>,
+
+    /// Remaining re-flood hops; decremented by each node that re-floods the LSA
+    /// and not propagated further once it reaches zero (unlimited when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flood_ttl: Option<u8>,
+
+    /// Originator's signature over the LSA contents (base64 ECDSA, optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+
+    /// Originator's certificate used to verify the signature (base64 DER)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_cert: Option<String>,
+
+    /// Seconds since the originator's view of the topology last changed
+    /// (used as a route selection tie-break when enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability_hint: Option<u64>,
 }
 
 /// Response when receiving an LSA
@@ -130,3 +437,183 @@ pub struct LsaResponse {
     pub status: String,
     pub message: String,
 }
+
+/// Time-to-converge after the most recent topology change
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConvergenceResponse {
+    /// False while a burst of topology changes is still settling
+    pub converged: bool,
+    /// Time from the first LSA of the last completed burst until routes stopped changing
+    pub last_convergence_ms: Option<u64>,
+    /// Number of accepted LSAs in the last completed burst
+    pub last_burst_lsa_count: u64,
+    /// How long the topology must stay unchanged before a burst counts as converged
+    pub quiet_period_ms: u64,
+}
+
+/// Routing side of GET /stats, from `RoutingTable::compute_stats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingStats {
+    /// Direct peers, whatever their status
+    pub total_peers: usize,
+    /// Direct peers whose link is up (Connected or Degraded)
+    pub connected_peers: usize,
+    /// LSAs in the link-state database
+    pub lsa_count: usize,
+    /// Nodes with a route from this one, including those only named as an LSA neighbor
+    pub reachable_nodes: usize,
+    /// Nodes with an LSA (or configured as a peer) but no route from this one
+    pub unreachable_nodes: usize,
+    /// Mean hop count over the routes to `reachable_nodes` (None if there are none)
+    pub average_path_length: Option<f64>,
+}
+
+/// Message counters since startup, as in /metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageStats {
+    pub sent: u64,
+    pub received: u64,
+    pub forwarded: u64,
+    pub dropped: u64,
+    pub duplicates: u64,
+}
+
+/// Response for GET /stats
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub node_id: String,
+    pub uptime_seconds: u64,
+    #[serde(flatten)]
+    pub routing: RoutingStats,
+    pub messages: MessageStats,
+    /// Destinations whose route back here differs from our route to them
+    pub path_asymmetries: Vec<PathAsymmetry>,
+}
+
+/// A destination whose forward and reverse routes take different paths
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathAsymmetry {
+    pub destination: String,
+    /// Our route to `destination`, from this node to it
+    pub forward: Vec<String>,
+    /// `destination`'s route back, reversed to also run from this node to it
+    /// (None if it has no route back)
+    pub reverse: Option<Vec<String>>,
+}
+
+/// A neighbor claim that isn't reciprocated by the other side's LSA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkAsymmetry {
+    /// Node whose LSA lists the link
+    pub node_id: String,
+    /// Neighbor whose LSA does not list `node_id` back
+    pub neighbor: String,
+}
+
+/// Response for GET /topology/asymmetries
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AsymmetriesResponse {
+    pub asymmetries: Vec<LinkAsymmetry>,
+    pub count: usize,
+}
+
+/// A node as described by its LSA in this gateway's link-state database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyNode {
+    pub node_id: String,
+    pub neighbors: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub neighbor_costs: BTreeMap<String, u32>,
+    pub sequence: u64,
+    pub epoch: u64,
+    #[serde(with = "systemtime_serialization")]
+    pub timestamp: Option<SystemTime>,
+}
+
+/// This gateway's view of the whole mesh (GET /topology)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologySnapshot {
+    /// Nodes with an LSA in the database, sorted by node_id
+    pub nodes: Vec<TopologyNode>,
+    /// Our direct peers, sorted by node_id
+    pub peers: Vec<PeerInfo>,
+}
+
+/// Query for GET /stats
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Peer whose LSA database the routes back are computed from
+    /// (default: our own)
+    pub compare_with: Option<String>,
+}
+
+/// Query for GET /topology/whatif
+#[derive(Debug, Deserialize)]
+pub struct WhatIfQuery {
+    pub from: String,
+    pub to: String,
+}
+
+/// Request for POST /message/trace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRequest {
+    pub to: String,
+}
+
+/// Probe passed hop to hop by a trace, like a message but with no content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceProbe {
+    pub to: String,
+    /// Nodes the probe has passed through, starting with the one that
+    /// started the trace; each hop appends itself
+    pub route: Vec<String>,
+    /// Remaining hops this probe may be passed on
+    pub ttl: u8,
+}
+
+/// Time a node on a traced path added
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceHop {
+    pub node_id: String,
+    /// The network round trip from the previous hop plus the node's own
+    /// handling, not counting the hops after it; all hops sum to the total
+    pub hop_latency_ms: f64,
+}
+
+/// Response for POST /message/trace; also what each hop answers the one
+/// before it with, for the hops from itself onward
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceResponse {
+    pub to: String,
+    /// Whether the probe got to `to`; if not, `hops` ends at the node that
+    /// couldn't pass it on, and `error` says why
+    pub reached: bool,
+    /// In path order, starting with the answering node
+    pub hops: Vec<TraceHop>,
+    /// Time from the probe arriving at the answering node to its answer
+    pub elapsed_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetail>,
+}
+
+/// Whether this node can currently route to a given node (GET /peer/:id/reachable)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReachabilityResponse {
+    pub reachable: bool,
+    /// Number of hops to the node (0 for ourselves or when unreachable)
+    pub hops: usize,
+    /// Full path starting with this node (empty when unreachable)
+    pub path: Vec<String>,
+}
+
+/// Route a given source would take, computed from this node's topology view
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhatIfResponse {
+    pub from: String,
+    pub to: String,
+    pub reachable: bool,
+    /// Full path including `from` (empty when unreachable)
+    pub path: Vec<String>,
+    /// Sum of the link costs along the path
+    pub cost: Option<u32>,
+}
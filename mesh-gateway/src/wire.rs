@@ -0,0 +1,171 @@
+use axum::{
+    async_trait,
+    body::{Bytes, HttpBody},
+    extract::FromRequest,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    BoxError, Json,
+};
+use reqwest::RequestBuilder;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// Content type of MessagePack bodies
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Encoding of the bodies gateways send each other
+///
+/// Receivers accept either, so nodes with different settings interoperate;
+/// responses come back in the encoding the request asked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireEncoding {
+    #[default]
+    Json,
+    Msgpack,
+}
+
+impl WireEncoding {
+    /// Attach `body` to `request` in this encoding, asking for the response in it too
+    ///
+    /// Falls back to JSON if the body can't be encoded as MessagePack.
+    pub fn encode<T: Serialize>(self, request: RequestBuilder, body: &T) -> RequestBuilder {
+        match self.body(body) {
+            Ok((content_type, bytes)) => request
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::ACCEPT, self.content_type())
+                .body(bytes),
+            // Let reqwest report the failure when the request is sent
+            Err(_) => request.json(body),
+        }
+    }
+
+    /// Encode `body`, returning it with its Content-Type
+    ///
+    /// Falls back to JSON if the body can't be encoded as MessagePack.
+    pub fn body<T: Serialize>(self, body: &T) -> Result<(&'static str, Vec<u8>), serde_json::Error> {
+        if self == WireEncoding::Msgpack {
+            match rmp_serde::to_vec_named(body) {
+                Ok(bytes) => return Ok((MSGPACK_CONTENT_TYPE, bytes)),
+                Err(e) => tracing::warn!("Failed to encode body as MessagePack, sending JSON: {}", e),
+            }
+        }
+        Ok((JSON_CONTENT_TYPE, serde_json::to_vec(body)?))
+    }
+
+    /// Content type of bodies in this encoding
+    pub fn content_type(self) -> &'static str {
+        match self {
+            WireEncoding::Json => JSON_CONTENT_TYPE,
+            WireEncoding::Msgpack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Errors reading a peer's response body
+#[derive(Debug, Error)]
+pub enum WireError {
+    #[error("{0}")]
+    Body(#[from] reqwest::Error),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid MessagePack: {0}")]
+    Msgpack(#[from] rmp_serde::decode::Error),
+}
+
+/// Decode a peer's response body according to its Content-Type
+pub async fn read_body<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, WireError> {
+    let msgpack = is_msgpack(response.headers().get(header::CONTENT_TYPE));
+    let bytes = response.bytes().await?;
+    decode(msgpack, &bytes)
+}
+
+/// Decode a body received with the given Content-Type
+pub fn decode_body<T: DeserializeOwned>(content_type: Option<&HeaderValue>, bytes: &[u8]) -> Result<T, WireError> {
+    decode(is_msgpack(content_type), bytes)
+}
+
+fn decode<T: DeserializeOwned>(msgpack: bool, bytes: &[u8]) -> Result<T, WireError> {
+    if msgpack {
+        Ok(rmp_serde::from_slice(bytes)?)
+    } else {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+fn is_msgpack(value: Option<&HeaderValue>) -> bool {
+    value
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(MSGPACK_CONTENT_TYPE))
+}
+
+/// Request body in either encoding, chosen by its Content-Type
+///
+/// Anything not declared as MessagePack is handled exactly like `Json<T>`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wire<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Wire<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_msgpack(request.headers().get(header::CONTENT_TYPE)) {
+            let Json(value) = Json::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+            return Ok(Wire(value));
+        }
+
+        let bytes = Bytes::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+        rmp_serde::from_slice(&bytes).map(Wire).map_err(|e| {
+            (StatusCode::UNPROCESSABLE_ENTITY, format!("Failed to deserialize the MessagePack body: {}", e)).into_response()
+        })
+    }
+}
+
+/// Middleware that re-encodes a JSON response as MessagePack when the
+/// request's Accept header asks for it
+pub async fn negotiate_encoding<B>(request: Request<B>, next: Next<B>) -> Response {
+    let wants_msgpack = is_msgpack(request.headers().get(header::ACCEPT));
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !wants_msgpack || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let transcoded = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).map_err(|e| e.to_string()))
+        .and_then(|value| rmp_serde::to_vec_named(&value).map_err(|e| e.to_string()));
+
+    match transcoded {
+        Ok(bytes) => {
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(MSGPACK_CONTENT_TYPE));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, axum::body::boxed(axum::body::Full::from(bytes)))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to re-encode response as MessagePack: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+